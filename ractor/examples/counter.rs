@@ -4,7 +4,9 @@
 // LICENSE-MIT file in the root directory of this source tree.
 
 //! A basic counting agent. Demonstrates remote procedure calls to interact
-//! with the agent externally and safely acquire the "count"
+//! with the agent externally and safely acquire the "count", retrying via
+//! [ractor::rpc::call_with_retries] rather than failing the whole loop on a
+//! single slow or dropped reply
 //!
 //! Execute with
 //!
@@ -14,6 +16,7 @@
 
 extern crate ractor;
 
+use ractor::rpc::{CallResult, CancellationToken};
 use ractor::{Actor, ActorRef, RpcReplyPort};
 use tokio::time::Duration;
 
@@ -64,6 +67,11 @@ async fn main() {
         .await
         .expect("Failed to start actor!");
 
+    // a real handler never stalls, but `call_with_retries` means this loop
+    // doesn't have to trust that -- a slow/unreliable Retrieve just costs a
+    // retry instead of hanging the example forever
+    let cancellation = CancellationToken::new();
+
     // +5 +10 -5 a few times, printing the value via RPC
     for _i in 0..4 {
         actor
@@ -76,15 +84,20 @@ async fn main() {
             .send_message(CounterMessage::Decrement(5))
             .expect("Failed to send message");
 
-        let rpc_result = actor
-            .call(CounterMessage::Retrieve, Some(Duration::from_millis(10)))
+        match actor
+            .call_with_retries(
+                CounterMessage::Retrieve,
+                3,
+                Duration::from_millis(10),
+                &cancellation,
+            )
             .await
-            .expect("Failed to send RPC");
-
-        println!(
-            "Count is: {}",
-            rpc_result.expect("RPC failed to reply successfully")
-        );
+        {
+            CallResult::Success(count) => println!("Count is: {count}"),
+            CallResult::Timeout => panic!("RPC timed out after all retries"),
+            CallResult::Cancelled => panic!("RPC was cancelled"),
+            CallResult::ActorGone => panic!("Actor stopped before replying"),
+        }
     }
 
     actor.stop(None);
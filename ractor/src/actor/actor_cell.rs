@@ -0,0 +1,346 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! [ActorCell] is the type-erased handle to a running actor, and [ActorRef]
+//! is the typed handle application code actually interacts with.
+
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc;
+
+use crate::actor::errors::MessagingErr;
+use crate::actor::messages::{Signal, SupervisionEvent};
+use crate::actor::Actor;
+use crate::actor_id::ActorId;
+use crate::{ActorName, Message};
+
+/// The lifecycle status of an actor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ActorStatus {
+    /// Created, but not yet started
+    Unstarted = 0,
+    /// `pre_start` is currently executing
+    Starting = 1,
+    /// Actively processing messages
+    Running = 2,
+    /// A restart is in progress (state is being rebuilt)
+    Upgrading = 3,
+    /// A stop has been requested and is draining in-flight work
+    Stopping = 4,
+    /// Fully terminated, no further messages will be processed
+    Stopped = 5,
+}
+
+impl From<u8> for ActorStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Unstarted,
+            1 => Self::Starting,
+            2 => Self::Running,
+            3 => Self::Upgrading,
+            4 => Self::Stopping,
+            _ => Self::Stopped,
+        }
+    }
+}
+
+/// The set of [ActorStatus] values in which an actor is still considered "alive"
+/// and eligible to receive messages
+pub const ACTIVE_STATES: &[ActorStatus] = &[
+    ActorStatus::Starting,
+    ActorStatus::Running,
+    ActorStatus::Upgrading,
+];
+
+type BoxedMessage = Box<dyn Any + Send>;
+
+pub(crate) struct ActorPortSet {
+    pub(crate) signal_tx: mpsc::Sender<Signal>,
+    pub(crate) stop_tx: mpsc::Sender<Option<String>>,
+    pub(crate) supervision_tx: mpsc::Sender<SupervisionEvent>,
+    pub(crate) message_tx: mpsc::UnboundedSender<BoxedMessage>,
+}
+
+struct ActorCellInner {
+    id: ActorId,
+    name: Option<ActorName>,
+    status: AtomicU8,
+    ports: ActorPortSet,
+    supervisors: Mutex<Vec<ActorCell>>,
+}
+
+/// A non-owning reference to an [ActorCell]. Held by background tasks (such as
+/// timers) that should stop acting once the actor they're attached to
+/// is gone, without themselves keeping it alive.
+#[derive(Clone)]
+pub struct WeakActorCell {
+    inner: std::sync::Weak<ActorCellInner>,
+}
+
+impl WeakActorCell {
+    /// Try to recover a strong [ActorCell], if the actor is still alive
+    pub fn upgrade(&self) -> Option<ActorCell> {
+        self.inner.upgrade().map(|inner| ActorCell { inner })
+    }
+}
+
+/// A type-erased handle to a running actor. Cloning an [ActorCell] is cheap
+/// (it's an `Arc` underneath) and gives another reference to the same actor,
+/// which is what's stored in supervisor child lists, the [crate::registry],
+/// and [crate::pg] process groups.
+#[derive(Clone)]
+pub struct ActorCell {
+    inner: Arc<ActorCellInner>,
+}
+
+impl PartialEq for ActorCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.id == other.inner.id
+    }
+}
+impl Eq for ActorCell {}
+
+impl std::fmt::Debug for ActorCell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ActorCell")
+            .field("id", &self.inner.id)
+            .field("name", &self.inner.name)
+            .field("status", &self.status())
+            .finish()
+    }
+}
+
+impl ActorCell {
+    pub(crate) fn new(name: Option<ActorName>, ports: ActorPortSet) -> Self {
+        Self {
+            inner: Arc::new(ActorCellInner {
+                id: ActorId::new(),
+                name,
+                status: AtomicU8::new(ActorStatus::Unstarted as u8),
+                ports,
+                supervisors: Mutex::new(vec![]),
+            }),
+        }
+    }
+
+    /// This actor's process-unique identifier
+    pub fn get_id(&self) -> ActorId {
+        self.inner.id
+    }
+
+    /// This actor's registered name, if it has one
+    pub fn get_name(&self) -> Option<ActorName> {
+        self.inner.name
+    }
+
+    /// The actor's current lifecycle status
+    pub fn status(&self) -> ActorStatus {
+        ActorStatus::from(self.inner.status.load(Ordering::SeqCst))
+    }
+
+    pub(crate) fn set_status(&self, status: ActorStatus) {
+        self.inner.status.store(status as u8, Ordering::SeqCst);
+    }
+
+    /// Whether this actor is in one of the [ACTIVE_STATES]
+    pub fn is_active(&self) -> bool {
+        ACTIVE_STATES.contains(&self.status())
+    }
+
+    /// Obtain a [WeakActorCell] that doesn't keep this actor alive
+    pub fn downgrade(&self) -> WeakActorCell {
+        WeakActorCell {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    /// Register another actor as this actor's supervisor, so it's notified
+    /// of this actor's lifecycle events
+    pub fn link_to_supervisor(&self, supervisor: ActorCell) {
+        self.inner.supervisors.lock().unwrap().push(supervisor);
+    }
+
+    /// The actors currently supervising this actor
+    pub fn supervisors(&self) -> Vec<ActorCell> {
+        self.inner.supervisors.lock().unwrap().clone()
+    }
+
+    /// Notify every linked supervisor of a [SupervisionEvent] concerning this actor
+    pub(crate) fn notify_supervisors(&self, evt: SupervisionEvent) {
+        for supervisor in self.supervisors() {
+            let _ = supervisor.inner.ports.supervision_tx.try_send(evt.clone());
+        }
+    }
+
+    /// Send the highest-priority [Signal::Kill], interrupting whatever the actor
+    /// is currently doing
+    pub fn kill(&self) {
+        let _ = self.inner.ports.signal_tx.try_send(Signal::Kill);
+    }
+
+    /// Request a graceful stop, with an optional reason that's made available
+    /// to `post_stop`
+    pub fn stop(&self, reason: Option<String>) {
+        let _ = self.inner.ports.stop_tx.try_send(reason);
+    }
+
+    /// Enqueue a type-erased message onto this actor's message port. Prefer
+    /// [ActorRef::send_message] when the concrete actor type is known, since
+    /// it avoids the downcast entirely.
+    pub(crate) fn send_boxed_message(&self, msg: BoxedMessage) -> Result<(), MessagingErr<()>> {
+        self.inner
+            .ports
+            .message_tx
+            .send(msg)
+            .map_err(|_| MessagingErr::ChannelClosed)
+    }
+
+    pub(crate) fn send_supervisor_evt(
+        &self,
+        evt: SupervisionEvent,
+    ) -> Result<(), MessagingErr<SupervisionEvent>> {
+        self.inner
+            .ports
+            .supervision_tx
+            .try_send(evt)
+            .map_err(|e| match e {
+                mpsc::error::TrySendError::Closed(evt) => MessagingErr::SendError(evt),
+                mpsc::error::TrySendError::Full(evt) => MessagingErr::SendError(evt),
+            })
+    }
+}
+
+/// A typed handle to a running actor of type `TActor`. This is the handle
+/// application code holds onto and sends messages through; internally it's
+/// just an [ActorCell] plus the knowledge of which concrete message type
+/// to box/unbox.
+pub struct ActorRef<TActor>
+where
+    TActor: Actor,
+{
+    pub(crate) cell: ActorCell,
+    _marker: PhantomData<TActor>,
+}
+
+impl<TActor> Clone for ActorRef<TActor>
+where
+    TActor: Actor,
+{
+    fn clone(&self) -> Self {
+        Self {
+            cell: self.cell.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<TActor> From<ActorCell> for ActorRef<TActor>
+where
+    TActor: Actor,
+{
+    fn from(cell: ActorCell) -> Self {
+        Self {
+            cell,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<TActor> ActorRef<TActor>
+where
+    TActor: Actor,
+{
+    /// The underlying type-erased [ActorCell] for this actor
+    pub fn get_cell(&self) -> ActorCell {
+        self.cell.clone()
+    }
+
+    /// This actor's process-unique identifier
+    pub fn get_id(&self) -> ActorId {
+        self.cell.get_id()
+    }
+
+    /// Send a strongly-typed message to the actor
+    pub fn send_message(&self, msg: TActor::Msg) -> Result<(), MessagingErr<TActor::Msg>> {
+        self.cell
+            .send_boxed_message(Box::new(msg))
+            .map_err(|_| MessagingErr::ChannelClosed)
+    }
+
+    /// Request a graceful stop of the actor
+    pub fn stop(&self, reason: Option<String>) {
+        self.cell.stop(reason)
+    }
+
+    /// Immediately terminate the actor
+    pub fn kill(&self) {
+        self.cell.kill()
+    }
+
+    /// The actor's current lifecycle status
+    pub fn status(&self) -> ActorStatus {
+        self.cell.status()
+    }
+
+    /// Send a request and await a single reply, as built by [crate::rpc::call].
+    /// Resolves to `Ok(None)` if the call times out before a reply arrives.
+    pub async fn call<TReply, F>(
+        &self,
+        msg_builder: F,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<Option<TReply>, MessagingErr<TActor::Msg>>
+    where
+        F: FnOnce(crate::RpcReplyPort<TReply>) -> TActor::Msg,
+    {
+        crate::rpc::call(self, msg_builder, timeout).await
+    }
+
+    /// Like [ActorRef::call], but retries on a per-attempt timeout and
+    /// supports out-of-band cancellation. See [crate::rpc::call_with_retries].
+    pub async fn call_with_retries<TReply, F>(
+        &self,
+        msg_builder: F,
+        attempts: usize,
+        per_attempt_timeout: std::time::Duration,
+        cancellation: &crate::rpc::CancellationToken,
+    ) -> crate::rpc::CallResult<TReply>
+    where
+        F: Fn(crate::RpcReplyPort<TReply>) -> TActor::Msg,
+    {
+        crate::rpc::call_with_retries(self, msg_builder, attempts, per_attempt_timeout, cancellation)
+            .await
+    }
+
+    /// Repeatedly enqueue a message, built from `msg_factory`, onto this
+    /// actor's own message port every `interval`, until the returned
+    /// [crate::time::TimerHandle] is dropped or aborted. See
+    /// [crate::time::send_interval].
+    pub fn send_interval<F>(&self, interval: std::time::Duration, msg_factory: F) -> crate::time::TimerHandle
+    where
+        F: Fn() -> TActor::Msg + Send + 'static,
+    {
+        crate::time::send_interval(self.cell.downgrade(), interval, msg_factory)
+    }
+
+    /// Enqueue a single message onto this actor's own message port after
+    /// `delay` elapses, unless cancelled first. See [crate::time::send_after].
+    pub fn send_after<F>(&self, delay: std::time::Duration, msg_factory: F) -> crate::time::TimerHandle
+    where
+        F: FnOnce() -> TActor::Msg + Send + 'static,
+    {
+        crate::time::send_after(self.cell.downgrade(), delay, msg_factory)
+    }
+}
+
+pub(crate) fn downcast_message<TMsg>(boxed: BoxedMessage) -> Option<TMsg>
+where
+    TMsg: Message,
+{
+    boxed.downcast::<TMsg>().ok().map(|b| *b)
+}
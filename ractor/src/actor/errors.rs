@@ -0,0 +1,61 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! Error types returned by the actor subsystem
+
+use std::fmt::Debug;
+
+/// An error which occurred during messaging (sending/receiving)
+#[derive(thiserror::Error, Debug)]
+pub enum MessagingErr<TMessage> {
+    /// The channel had an error during sending, generally means the
+    /// channel closed because the actor is stopped or stopping
+    #[error("Channel closed")]
+    ChannelClosed,
+
+    /// The message failed to deserialize or serialize
+    #[error("Invalid actor type")]
+    InvalidActorType,
+
+    /// The message send failed, returning the original message
+    #[error("Send error")]
+    SendError(TMessage),
+}
+
+/// Error type which is returned from failed actor spawns
+#[derive(thiserror::Error, Debug)]
+pub enum SpawnErr {
+    /// The actor panic'd or returned an error during `pre_start`, meaning
+    /// it failed to start up
+    #[error("Actor panicked during startup: {0}")]
+    StartupPanic(String),
+
+    /// The actor failed to start up for a reason that's not a panic
+    #[error("Actor failed to start: {0}")]
+    StartupFailed(String),
+
+    /// An actor cannot be started > once, so if a struct is re-used the 2nd+
+    /// start will fail
+    #[error("Actor cannot be started more than once")]
+    ActorAlreadyStarted,
+
+    /// Failed to register the actor in the registry, generally because
+    /// the name is already claimed
+    #[error("Failed to register actor in registry: {0}")]
+    StartupRegistrationFailed(String),
+}
+
+/// An error which occurred during an actor's lifecycle, most notably
+/// while processing messages, supervision events, or during shutdown
+#[derive(thiserror::Error, Debug)]
+pub enum ActorErr {
+    /// The actor panic'd during execution
+    #[error("Actor panicked: {0}")]
+    Panic(String),
+
+    /// The actor was cancelled/killed out-of-band
+    #[error("Actor cancelled")]
+    Cancelled,
+}
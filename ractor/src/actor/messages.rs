@@ -0,0 +1,56 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! The built-in message types every actor understands, in addition to its
+//! own user-defined [crate::Message]
+
+use crate::actor::actor_cell::ActorCell;
+use crate::actor::errors::ActorErr;
+
+/// A signal is the highest-priority message an actor can receive. Signals
+/// interrupt whatever the actor is currently doing, including in-flight
+/// async work, and cannot be intercepted or ignored. For an actor running in
+/// [crate::ExecutionMode::Blocking], a signal lands between messages instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// Immediately terminate the actor, dropping any in-progress work
+    Kill,
+}
+
+/// A notification sent from a child actor to its supervisor(s) when the
+/// child starts, stops, or fails.
+#[derive(Debug, Clone)]
+pub enum SupervisionEvent {
+    /// An actor was started
+    ActorStarted(ActorCell),
+
+    /// An actor terminated, with an optional exit reason
+    ActorTerminated(ActorCell, Option<String>),
+
+    /// An actor failed/panic'd during processing
+    ActorFailed(ActorCell, ActorErrMessage),
+
+    /// A process group changed membership, some actors joined and/or left
+    ProcessGroupChanged {
+        /// The group which changed
+        group: crate::GroupName,
+        /// Actors which joined the group
+        joined: Vec<ActorCell>,
+        /// Actors which left the group (generally because they terminated)
+        left: Vec<ActorCell>,
+    },
+}
+
+/// A cloneable representation of an [ActorErr], since the original error
+/// isn't necessarily `Clone` (and [SupervisionEvent] needs to be, to support
+/// fan-out to multiple supervisors)
+#[derive(Debug, Clone)]
+pub struct ActorErrMessage(pub String);
+
+impl From<ActorErr> for ActorErrMessage {
+    fn from(err: ActorErr) -> Self {
+        Self(err.to_string())
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! The core actor abstraction: the [Actor] trait application code implements,
+//! and the [ActorRuntime] that drives an actor's message loop once spawned.
+
+pub mod actor_cell;
+pub mod errors;
+pub mod messages;
+pub mod supervisor;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::actor::actor_cell::{downcast_message, ActorCell, ActorPortSet, ActorRef, ActorStatus};
+use crate::actor::errors::{ActorErr, SpawnErr};
+use crate::actor::messages::{Signal, SupervisionEvent};
+use crate::{ActorName, Message, State};
+
+/// Where an actor's message loop runs, following quickwit-actors' `Actor::runner`
+/// distinction between actors that are fine sharing the async runtime's
+/// worker pool and actors whose `handle` does long synchronous/CPU-bound work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Run on the shared tokio runtime, like any other `async fn`. The right
+    /// choice unless `handle` blocks the thread for a meaningful amount of time.
+    #[default]
+    Async,
+    /// Run the message loop on a dedicated OS thread (via
+    /// [tokio::task::spawn_blocking]), so long synchronous work in `handle`
+    /// doesn't stall the tokio worker pool. Signals and stop requests are
+    /// still polled between messages, so they land promptly.
+    Blocking,
+}
+
+/// `Actor` is the trait every actor implementation provides. It defines the
+/// actor's message and state types, plus the lifecycle callbacks that are
+/// invoked as the actor starts, processes messages, and stops.
+///
+/// Only [Actor::handle] is required; the rest have no-op defaults.
+#[async_trait::async_trait]
+pub trait Actor: Sized + Sync + Send + 'static {
+    /// The message type this actor processes
+    type Msg: Message;
+
+    /// The internal state carried between message handler invocations
+    type State: State;
+
+    /// Where this actor's message loop runs. Defaults to [ExecutionMode::Async];
+    /// override to [ExecutionMode::Blocking] for actors doing long
+    /// synchronous/CPU-bound work per message.
+    fn execution_mode() -> ExecutionMode {
+        ExecutionMode::Async
+    }
+
+    /// Invoked once, before the actor starts processing messages, to build
+    /// its initial [Actor::State]. A panic here fails the spawn outright
+    /// ([SpawnErr::StartupPanic]) and is not reported to the supervisor passed
+    /// to [Actor::spawn_linked] (see the crate docs).
+    async fn pre_start(&self, myself: ActorRef<Self>) -> Self::State;
+
+    /// Invoked once, after `pre_start` but before the first message is
+    /// processed. Failures here *are* reported to a linked supervisor.
+    async fn post_start(&self, _myself: ActorRef<Self>, _state: &mut Self::State) {}
+
+    /// The actor's main message handler
+    async fn handle(&self, myself: ActorRef<Self>, message: Self::Msg, state: &mut Self::State);
+
+    /// Invoked when a linked child reports a [SupervisionEvent]. Default
+    /// behavior ignores the event; supervisors override this to apply a
+    /// restart strategy (see [supervisor::Supervisor]).
+    async fn handle_supervisor_evt(
+        &self,
+        _myself: ActorRef<Self>,
+        _message: SupervisionEvent,
+        _state: &mut Self::State,
+    ) {
+    }
+
+    /// Invoked once the actor has stopped processing messages, for cleanup
+    async fn post_stop(&self, _myself: ActorRef<Self>, _state: &mut Self::State) {}
+
+    /// Spawn this actor, starting its message loop on the current tokio runtime
+    async fn spawn(
+        name: Option<ActorName>,
+        actor: Self,
+    ) -> Result<(ActorRef<Self>, JoinHandle<()>), SpawnErr> {
+        Self::spawn_linked(name, actor, None).await
+    }
+
+    /// Spawn this actor and immediately link it to `supervisor`, so the
+    /// supervisor is notified of this actor's start/stop/failure
+    async fn spawn_linked(
+        name: Option<ActorName>,
+        actor: Self,
+        supervisor: Option<ActorCell>,
+    ) -> Result<(ActorRef<Self>, JoinHandle<()>), SpawnErr> {
+        let (signal_tx, signal_rx) = mpsc::channel(1);
+        let (stop_tx, stop_rx) = mpsc::channel(1);
+        let (supervision_tx, supervision_rx) = mpsc::channel(16);
+        let (message_tx, message_rx) = mpsc::unbounded_channel();
+
+        let cell = ActorCell::new(
+            name,
+            ActorPortSet {
+                signal_tx,
+                stop_tx,
+                supervision_tx,
+                message_tx,
+            },
+        );
+        if let Some(name) = name {
+            crate::registry::register(name, cell.clone())
+                .map_err(SpawnErr::StartupRegistrationFailed)?;
+        }
+        if let Some(supervisor) = &supervisor {
+            cell.link_to_supervisor(supervisor.clone());
+        }
+
+        let myself_ref: ActorRef<Self> = cell.clone().into();
+
+        cell.set_status(ActorStatus::Starting);
+        let state = match std::panic::AssertUnwindSafe(actor.pre_start(myself_ref.clone()))
+            .catch_unwind()
+            .await
+        {
+            Ok(state) => state,
+            Err(_) => {
+                // `pre_start` never got far enough to hand off to `ActorRuntime`,
+                // whose `finish` is what normally marks the cell stopped and
+                // unregisters its name. Do that ourselves here, or the name
+                // stays claimed by a `Starting` cell forever (`Starting` is an
+                // [ACTIVE_STATES] member) and can never be spawned again.
+                cell.set_status(ActorStatus::Stopped);
+                if let Some(name) = name {
+                    crate::registry::unregister(name);
+                }
+                crate::pg::prune(&cell);
+                return Err(SpawnErr::StartupPanic("panic in pre_start".to_string()));
+            }
+        };
+        cell.notify_supervisors(SupervisionEvent::ActorStarted(cell.clone()));
+
+        let runtime = ActorRuntime {
+            actor,
+            cell: cell.clone(),
+            signal_rx,
+            stop_rx,
+            supervision_rx,
+            message_rx,
+        };
+
+        let join_handle = match Self::execution_mode() {
+            ExecutionMode::Async => tokio::spawn(runtime.run(state)),
+            ExecutionMode::Blocking => {
+                tokio::task::spawn_blocking(move || runtime.run_blocking(state))
+            }
+        };
+
+        Ok((myself_ref, join_handle))
+    }
+}
+
+use futures::FutureExt;
+
+/// The task that owns an actor's state and drives its message loop once
+/// spawned. Returned indirectly from [Actor::spawn] via the `JoinHandle`
+/// that completes once this runtime exits.
+pub struct ActorRuntime<TActor>
+where
+    TActor: Actor,
+{
+    actor: TActor,
+    cell: ActorCell,
+    signal_rx: mpsc::Receiver<Signal>,
+    stop_rx: mpsc::Receiver<Option<String>>,
+    supervision_rx: mpsc::Receiver<SupervisionEvent>,
+    message_rx: mpsc::UnboundedReceiver<Box<dyn std::any::Any + Send>>,
+}
+
+impl<TActor> ActorRuntime<TActor>
+where
+    TActor: Actor,
+{
+    async fn run(mut self, mut state: TActor::State) {
+        let myself: ActorRef<TActor> = self.cell.clone().into();
+
+        self.cell.set_status(ActorStatus::Running);
+        let mut panicked = guarded(self.actor.post_start(myself.clone(), &mut state))
+            .await
+            .err();
+
+        let exit_reason = if panicked.is_some() {
+            None
+        } else {
+            loop {
+                tokio::select! {
+                    biased;
+
+                    Some(Signal::Kill) = self.signal_rx.recv() => {
+                        break None;
+                    }
+                    Some(reason) = self.stop_rx.recv() => {
+                        break Some(reason);
+                    }
+                    Some(evt) = self.supervision_rx.recv() => {
+                        // a kill interrupts the in-flight handler, dropping its work
+                        let res = tokio::select! {
+                            biased;
+                            Some(Signal::Kill) = self.signal_rx.recv() => break None,
+                            res = guarded(self.actor.handle_supervisor_evt(myself.clone(), evt, &mut state)) => res,
+                        };
+                        if let Err(err) = res {
+                            panicked = Some(err);
+                            break None;
+                        }
+                    }
+                    Some(boxed) = self.message_rx.recv() => {
+                        if let Some(msg) = downcast_message::<TActor::Msg>(boxed) {
+                            let res = tokio::select! {
+                                biased;
+                                Some(Signal::Kill) = self.signal_rx.recv() => break None,
+                                res = guarded(self.actor.handle(myself.clone(), msg, &mut state)) => res,
+                            };
+                            if let Err(err) = res {
+                                panicked = Some(err);
+                                break None;
+                            }
+                        }
+                    }
+                    else => break None,
+                }
+            }
+        };
+
+        self.finish(myself, state, exit_reason.flatten(), panicked).await;
+    }
+
+    /// Drive the message loop on a dedicated OS thread, for
+    /// [ExecutionMode::Blocking] actors. `handle`/`handle_supervisor_evt` are
+    /// still `async fn`s (so the same `Actor` impl works in either mode), but
+    /// here they're driven to completion synchronously via [futures::executor::block_on]
+    /// rather than yielded to the tokio scheduler. Between every message the
+    /// high-priority signal/stop channels are polled, so a kill/stop lands as
+    /// soon as the current `handle` call returns, ahead of any queued
+    /// messages; a `handle` call already running is not interrupted.
+    fn run_blocking(mut self, mut state: TActor::State) {
+        let myself: ActorRef<TActor> = self.cell.clone().into();
+
+        self.cell.set_status(ActorStatus::Running);
+        let mut panicked =
+            futures::executor::block_on(guarded(self.actor.post_start(myself.clone(), &mut state)))
+                .err();
+
+        let exit_reason = if panicked.is_some() {
+            None
+        } else {
+            'outer: loop {
+                if matches!(self.signal_rx.try_recv(), Ok(Signal::Kill)) {
+                    break None;
+                }
+                if let Ok(reason) = self.stop_rx.try_recv() {
+                    break Some(reason);
+                }
+                match self.supervision_rx.try_recv() {
+                    Ok(evt) => {
+                        if let Err(err) = futures::executor::block_on(guarded(
+                            self.actor.handle_supervisor_evt(myself.clone(), evt, &mut state),
+                        )) {
+                            panicked = Some(err);
+                            break 'outer None;
+                        }
+                        continue;
+                    }
+                    Err(mpsc::error::TryRecvError::Disconnected) => break 'outer None,
+                    Err(mpsc::error::TryRecvError::Empty) => {}
+                }
+                match self.message_rx.try_recv() {
+                    Ok(boxed) => {
+                        if let Some(msg) = downcast_message::<TActor::Msg>(boxed) {
+                            if let Err(err) = futures::executor::block_on(guarded(
+                                self.actor.handle(myself.clone(), msg, &mut state),
+                            )) {
+                                panicked = Some(err);
+                                break 'outer None;
+                            }
+                        }
+                    }
+                    Err(mpsc::error::TryRecvError::Disconnected) => break 'outer None,
+                    Err(mpsc::error::TryRecvError::Empty) => {
+                        // nothing to do right now: yield the OS thread briefly
+                        // rather than spin, while staying responsive to signals
+                        std::thread::sleep(std::time::Duration::from_millis(5));
+                    }
+                }
+            }
+        };
+
+        futures::executor::block_on(self.finish(myself, state, exit_reason.flatten(), panicked));
+    }
+
+    /// Shared shutdown sequence for both [Self::run] and [Self::run_blocking]:
+    /// run `post_stop`, tear it out of the registry/process groups, mark the
+    /// actor stopped, and notify any supervisors. If `panicked` (or
+    /// `post_stop` itself panics) is set, the linked supervisors are told
+    /// [SupervisionEvent::ActorFailed] rather than [SupervisionEvent::ActorTerminated],
+    /// so a panicking child is actually restarted rather than just quietly
+    /// leaving the actor wedged with a stale registry entry.
+    async fn finish(
+        mut self,
+        myself: ActorRef<TActor>,
+        mut state: TActor::State,
+        exit_reason: Option<String>,
+        panicked: Option<ActorErr>,
+    ) {
+        self.cell.set_status(ActorStatus::Stopping);
+        let post_stop_panic = guarded(self.actor.post_stop(myself, &mut state)).await.err();
+
+        if let Some(name) = self.cell.get_name() {
+            crate::registry::unregister(name);
+        }
+        crate::pg::prune(&self.cell);
+        // only once the name is released, so a waiter on `Stopped` can reuse it
+        self.cell.set_status(ActorStatus::Stopped);
+
+        match panicked.or(post_stop_panic) {
+            Some(err) => self.cell.notify_supervisors(SupervisionEvent::ActorFailed(
+                self.cell.clone(),
+                err.into(),
+            )),
+            None => self
+                .cell
+                .notify_supervisors(SupervisionEvent::ActorTerminated(
+                    self.cell.clone(),
+                    exit_reason,
+                )),
+        }
+    }
+}
+
+/// Await `fut`, converting a panic into an [ActorErr::Panic] instead of
+/// unwinding the actor's runtime task. Used to guard every lifecycle
+/// callback past `pre_start` (which is guarded directly in [Actor::spawn_linked])
+/// so a panic anywhere in an actor's `handle` still runs [ActorRuntime::finish]
+/// and reports [SupervisionEvent::ActorFailed] to its supervisor.
+async fn guarded<F: std::future::Future>(fut: F) -> Result<F::Output, ActorErr> {
+    std::panic::AssertUnwindSafe(fut)
+        .catch_unwind()
+        .await
+        .map_err(panic_to_actor_err)
+}
+
+/// Convert a caught panic payload into an [ActorErr::Panic], keeping its
+/// message when the payload is a string
+pub(crate) fn panic_to_actor_err(panic: Box<dyn std::any::Any + Send>) -> ActorErr {
+    let msg = if let Some(s) = panic.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    };
+    ActorErr::Panic(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone)]
+    struct BlockingCounter {
+        handled: Arc<AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl Actor for BlockingCounter {
+        type Msg = ();
+        type State = ();
+
+        fn execution_mode() -> ExecutionMode {
+            ExecutionMode::Blocking
+        }
+
+        async fn pre_start(&self, _myself: ActorRef<Self>) -> Self::State {}
+
+        async fn handle(&self, _myself: ActorRef<Self>, _message: Self::Msg, _state: &mut Self::State) {
+            // genuinely blocks the OS thread, unlike a plain `.await` yield
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            self.handled.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn blocking_actor_processes_messages_on_a_dedicated_thread() {
+        let handled = Arc::new(AtomicU32::new(0));
+        let (actor, _join_handle) = BlockingCounter::spawn(
+            None,
+            BlockingCounter {
+                handled: handled.clone(),
+            },
+        )
+        .await
+        .unwrap();
+
+        for _ in 0..3 {
+            actor.send_message(()).unwrap();
+        }
+
+        for _ in 0..500 {
+            if handled.load(Ordering::SeqCst) == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert_eq!(handled.load(Ordering::SeqCst), 3);
+
+        actor.stop(None);
+    }
+}
@@ -0,0 +1,432 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! A first-class supervision subsystem, modeled on Erlang/OTP's
+//! `supervisor` behaviour: a [Supervisor] holds a fixed set of child specs
+//! plus a restart [Strategy], and reacts to [SupervisionEvent]s from its
+//! children by restarting them according to that strategy, bailing out (and
+//! escalating to its own supervisor) if restarts happen too fast.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+
+use crate::actor::actor_cell::{ActorCell, ActorRef, ActorStatus};
+use crate::actor::errors::{ActorErr, SpawnErr};
+use crate::actor::messages::{ActorErrMessage, SupervisionEvent};
+use crate::actor::Actor;
+use crate::ActorName;
+
+/// How a child should be treated when it terminates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Always restart the child, regardless of why it stopped
+    Permanent,
+    /// Restart only if the child terminated abnormally (failed/panic'd)
+    Transient,
+    /// Never restart the child
+    Temporary,
+}
+
+/// How a [Supervisor] reacts to one of its children terminating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Restart only the child that terminated
+    OneForOne,
+    /// Stop and restart every child
+    OneForAll,
+    /// Restart the terminated child, plus every child started after it
+    RestForOne,
+}
+
+/// The restart-intensity limit: if more than `max_restarts` occur within a
+/// rolling `max_seconds` window, the supervisor gives up and escalates to
+/// *its* supervisor rather than restarting again.
+#[derive(Debug, Clone, Copy)]
+pub struct RestartIntensity {
+    /// Maximum number of restarts tolerated within the window
+    pub max_restarts: usize,
+    /// The width of the rolling window, in seconds
+    pub max_seconds: u64,
+}
+
+impl Default for RestartIntensity {
+    fn default() -> Self {
+        Self {
+            max_restarts: 3,
+            max_seconds: 5,
+        }
+    }
+}
+
+/// Object-safe factory for (re)spawning a single child of a [Supervisor].
+/// Implemented generically by [ChildSpec] for any `Actor + Clone`.
+pub trait ChildFactory: Send + Sync {
+    /// The name the child should be registered under, if any
+    fn name(&self) -> Option<ActorName>;
+
+    /// This child's restart policy
+    fn restart_policy(&self) -> RestartPolicy;
+
+    /// (Re)spawn the child, linked to `supervisor`
+    fn respawn(&self, supervisor: ActorCell) -> BoxFuture<'static, Result<ActorCell, SpawnErr>>;
+}
+
+/// A declarative description of one child a [Supervisor] should start and
+/// supervise, built from a template actor instance that's cloned on every
+/// (re)spawn.
+pub struct ChildSpec<TActor>
+where
+    TActor: Actor + Clone,
+{
+    name: Option<ActorName>,
+    template: TActor,
+    restart: RestartPolicy,
+}
+
+impl<TActor> ChildSpec<TActor>
+where
+    TActor: Actor + Clone,
+{
+    /// Declare a new child spec from a template actor instance
+    pub fn new(name: Option<ActorName>, template: TActor, restart: RestartPolicy) -> Self {
+        Self {
+            name,
+            template,
+            restart,
+        }
+    }
+
+    /// Box this spec for inclusion in a [Supervisor]'s child list
+    pub fn boxed(self) -> Box<dyn ChildFactory>
+    where
+        TActor: 'static,
+    {
+        Box::new(self)
+    }
+}
+
+impl<TActor> ChildFactory for ChildSpec<TActor>
+where
+    TActor: Actor + Clone,
+{
+    fn name(&self) -> Option<ActorName> {
+        self.name
+    }
+
+    fn restart_policy(&self) -> RestartPolicy {
+        self.restart
+    }
+
+    fn respawn(&self, supervisor: ActorCell) -> BoxFuture<'static, Result<ActorCell, SpawnErr>> {
+        let name = self.name;
+        let actor = self.template.clone();
+        Box::pin(async move {
+            let (actor_ref, _handle) =
+                TActor::spawn_linked(name, actor, Some(supervisor)).await?;
+            Ok(actor_ref.get_cell())
+        })
+    }
+}
+
+struct ChildEntry {
+    spec: Box<dyn ChildFactory>,
+    cell: ActorCell,
+}
+
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Poll `cell` until it's fully [ActorStatus::Stopped] (its `finish()` has
+/// run to completion, including unregistering its name). A no-op if it's
+/// already stopped.
+async fn await_stopped(cell: &ActorCell) {
+    while cell.status() != ActorStatus::Stopped {
+        tokio::time::sleep(STOP_POLL_INTERVAL).await;
+    }
+}
+
+/// A supervisor watches a fixed set of children and applies a [Strategy]
+/// when one of them terminates. It's itself a plain [Actor] (with no user
+/// messages of its own) so it composes with the rest of the actor tree: a
+/// `Supervisor` can be supervised by another `Supervisor`.
+///
+/// Children are started, in order, during `pre_start` -- that order is what
+/// [Strategy::RestForOne] restarts from.
+pub struct Supervisor {
+    strategy: Strategy,
+    intensity: RestartIntensity,
+    specs: Mutex<Vec<Box<dyn ChildFactory>>>,
+    children: Mutex<Vec<ChildEntry>>,
+    restart_log: Mutex<VecDeque<Instant>>,
+}
+
+impl Supervisor {
+    /// Build a supervisor overseeing `specs`, applying `strategy` on child
+    /// termination and bailing out per `intensity`.
+    pub fn new(strategy: Strategy, intensity: RestartIntensity, specs: Vec<Box<dyn ChildFactory>>) -> Self {
+        Self {
+            strategy,
+            intensity,
+            specs: Mutex::new(specs),
+            children: Mutex::new(vec![]),
+            restart_log: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Spawn the supervisor itself, which in turn starts all of its children
+    pub async fn spawn(
+        name: Option<ActorName>,
+        strategy: Strategy,
+        intensity: RestartIntensity,
+        specs: Vec<Box<dyn ChildFactory>>,
+    ) -> Result<(ActorRef<Supervisor>, tokio::task::JoinHandle<()>), SpawnErr> {
+        Actor::spawn(name, Supervisor::new(strategy, intensity, specs)).await
+    }
+
+    /// Record a restart attempt and check whether the intensity limit has
+    /// been exceeded (more than `max_restarts` within the rolling window)
+    fn record_restart_and_check_intensity(&self) -> bool {
+        let now = Instant::now();
+        let window = Duration::from_secs(self.intensity.max_seconds);
+        let mut log = self.restart_log.lock().unwrap();
+        log.push_back(now);
+        while let Some(front) = log.front() {
+            if now.duration_since(*front) > window {
+                log.pop_front();
+            } else {
+                break;
+            }
+        }
+        log.len() > self.intensity.max_restarts
+    }
+
+    async fn restart_children(&self, supervisor: ActorCell, indices: Vec<usize>) {
+        // Stop every targeted child first, and wait for *all* of them to
+        // actually finish shutting down, before respawning any of them.
+        // Interleaving stop/respawn per child would leave an already-restarted
+        // sibling running its new generation alongside others still on their
+        // stale instance -- exactly the inconsistent-sibling state
+        // OneForAll/RestForOne exist to avoid.
+        let old_cells: Vec<ActorCell> = indices
+            .iter()
+            .filter_map(|&idx| {
+                let children = self.children.lock().unwrap();
+                children.get(idx).map(|c| c.cell.clone())
+            })
+            .collect();
+        for old_cell in &old_cells {
+            if old_cell.is_active() {
+                old_cell.stop(Some("restarting".to_string()));
+            }
+        }
+        // Wait for every old instance to actually finish shutting down
+        // (unregistering its name, if any) before respawning into the same
+        // name -- otherwise a respawn can race `finish()`'s
+        // registry::unregister, either failing the respawn's own register()
+        // call or having the old instance unregister the *new* instance's
+        // name out from under it.
+        for old_cell in &old_cells {
+            await_stopped(old_cell).await;
+        }
+
+        for idx in indices {
+            let respawn_fut = {
+                let children = self.children.lock().unwrap();
+                children[idx].spec.respawn(supervisor.clone())
+            };
+            match respawn_fut.await {
+                Ok(new_cell) => self.children.lock().unwrap()[idx].cell = new_cell,
+                Err(err) => {
+                    // The respawn itself failed (e.g. the new instance
+                    // panicked in `pre_start`), so there's no new child to
+                    // hand off to and no `ActorRuntime::finish` that will
+                    // ever report a `SupervisionEvent` for it. Treat this the
+                    // same as exceeding the restart intensity: escalate to
+                    // our own supervisor rather than leaving the slot
+                    // permanently wedged with no restart and no signal.
+                    supervisor.notify_supervisors(SupervisionEvent::ActorFailed(
+                        supervisor.clone(),
+                        ActorErrMessage::from(ActorErr::Panic(err.to_string())),
+                    ));
+                    supervisor.stop(Some(format!("child {idx} failed to respawn: {err}")));
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn on_child_down(&self, myself: &ActorRef<Supervisor>, child: ActorCell, failed: bool) {
+        let index = self
+            .children
+            .lock()
+            .unwrap()
+            .iter()
+            .position(|c| c.cell == child);
+        let Some(index) = index else {
+            // not one of our direct children (e.g. a grandchild's event bubbled up)
+            return;
+        };
+
+        let policy = self.children.lock().unwrap()[index].spec.restart_policy();
+        let should_restart = match policy {
+            RestartPolicy::Permanent => true,
+            RestartPolicy::Transient => failed,
+            RestartPolicy::Temporary => false,
+        };
+        if !should_restart {
+            return;
+        }
+
+        if self.record_restart_and_check_intensity() {
+            // restart intensity exceeded: give up and escalate to our own supervisor
+            myself
+                .get_cell()
+                .notify_supervisors(SupervisionEvent::ActorFailed(
+                    myself.get_cell(),
+                    ActorErrMessage::from(ActorErr::Cancelled),
+                ));
+            myself.stop(Some("restart intensity exceeded".to_string()));
+            return;
+        }
+
+        let targets = match self.strategy {
+            Strategy::OneForOne => vec![index],
+            Strategy::OneForAll => (0..self.children.lock().unwrap().len()).collect(),
+            Strategy::RestForOne => (index..self.children.lock().unwrap().len()).collect(),
+        };
+        self.restart_children(myself.get_cell(), targets).await;
+    }
+}
+
+#[async_trait::async_trait]
+impl Actor for Supervisor {
+    type Msg = ();
+    type State = ();
+
+    async fn pre_start(&self, myself: ActorRef<Self>) -> Self::State {
+        let specs = std::mem::take(&mut *self.specs.lock().unwrap());
+        for spec in specs {
+            match spec.respawn(myself.get_cell()).await {
+                Ok(cell) => self
+                    .children
+                    .lock()
+                    .unwrap()
+                    .push(ChildEntry { spec, cell }),
+                Err(err) => {
+                    // a child failing to start at all is treated the same as
+                    // the supervisor itself failing to start
+                    panic!("supervisor child failed to start: {err}");
+                }
+            }
+        }
+    }
+
+    async fn handle(&self, _myself: ActorRef<Self>, _message: Self::Msg, _state: &mut Self::State) {
+    }
+
+    async fn handle_supervisor_evt(
+        &self,
+        myself: ActorRef<Self>,
+        message: SupervisionEvent,
+        _state: &mut Self::State,
+    ) {
+        match message {
+            SupervisionEvent::ActorTerminated(child, _reason) => {
+                self.on_child_down(&myself, child, false).await;
+            }
+            SupervisionEvent::ActorFailed(child, _err) => {
+                self.on_child_down(&myself, child, true).await;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Flaky;
+
+    enum FlakyMsg {
+        Panic,
+    }
+
+    #[async_trait::async_trait]
+    impl Actor for Flaky {
+        type Msg = FlakyMsg;
+        type State = ();
+
+        async fn pre_start(&self, _myself: ActorRef<Self>) -> Self::State {}
+
+        async fn handle(&self, _myself: ActorRef<Self>, message: Self::Msg, _state: &mut Self::State) {
+            match message {
+                FlakyMsg::Panic => panic!("flaky actor panicking on purpose"),
+            }
+        }
+    }
+
+    async fn wait_until(mut check: impl FnMut() -> bool) {
+        for _ in 0..500 {
+            if check() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition never became true");
+    }
+
+    #[tokio::test]
+    async fn one_for_one_restarts_a_panicking_child() {
+        let name = "supervisor_test_one_for_one_flaky";
+        let spec = ChildSpec::new(Some(name), Flaky, RestartPolicy::Permanent).boxed();
+        let (_supervisor, _handle) = Supervisor::spawn(
+            None,
+            Strategy::OneForOne,
+            RestartIntensity::default(),
+            vec![spec],
+        )
+        .await
+        .expect("supervisor failed to start");
+
+        let first_id = crate::registry::where_is(name)
+            .expect("child should be registered")
+            .get_id();
+
+        let flaky_ref: ActorRef<Flaky> = crate::registry::where_is(name).unwrap().into();
+        flaky_ref.send_message(FlakyMsg::Panic).unwrap();
+
+        wait_until(|| {
+            crate::registry::where_is(name)
+                .map(|cell| cell.get_id() != first_id)
+                .unwrap_or(false)
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn restart_intensity_exceeded_stops_the_supervisor() {
+        let name = "supervisor_test_intensity_flaky";
+        let spec = ChildSpec::new(Some(name), Flaky, RestartPolicy::Permanent).boxed();
+        let intensity = RestartIntensity {
+            max_restarts: 0,
+            max_seconds: 5,
+        };
+        let (supervisor, _handle) =
+            Supervisor::spawn(None, Strategy::OneForOne, intensity, vec![spec])
+                .await
+                .expect("supervisor failed to start");
+
+        let flaky_ref: ActorRef<Flaky> = crate::registry::where_is(name)
+            .expect("child should be registered")
+            .into();
+        flaky_ref.send_message(FlakyMsg::Panic).unwrap();
+
+        wait_until(|| supervisor.status() == ActorStatus::Stopped).await;
+    }
+}
@@ -0,0 +1,30 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! [ActorId] is a process-unique identifier handed out to every actor when it is spawned.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_ACTOR_ID: AtomicU64 = AtomicU64::new(0);
+
+/// An actor's unique identifier, assigned at spawn time. Unlike [crate::ActorName],
+/// an [ActorId] is always present (names are optional) and is never reused within
+/// the lifetime of the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ActorId(u64);
+
+impl ActorId {
+    /// Allocate the next [ActorId] in sequence
+    pub(crate) fn new() -> Self {
+        Self(NEXT_ACTOR_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Display for ActorId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
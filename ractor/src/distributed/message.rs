@@ -0,0 +1,83 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! The wire format spoken between two [super::node::NodeServer]s, and the
+//! [RemoteMessage] bound a message type needs to opt into crossing that
+//! wire, since arbitrary `Any + Send` payloads can't be serialized.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Message;
+
+/// A message that's allowed to travel between nodes. Unlike a plain
+/// [Message], a `RemoteMessage` must be (de)serializable, since it has to
+/// survive being shipped over a TCP connection to a different process.
+pub trait RemoteMessage: Message + Serialize + for<'de> Deserialize<'de> {}
+impl<T: Message + Serialize + for<'de> Deserialize<'de>> RemoteMessage for T {}
+
+/// One frame of the node-to-node protocol. Every [WireEnvelope] is
+/// length-prefixed and JSON-encoded on the wire (see `node::send_envelope`/
+/// `node::recv_envelope`).
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum WireEnvelope {
+    /// Sent immediately after connecting, identifying the sending node
+    Hello { node_name: String },
+
+    /// A periodic liveness check; an absent heartbeat for too long is
+    /// treated the same as the connection dropping
+    Heartbeat,
+
+    /// Deliver a message to the named actor on the receiving node
+    Deliver {
+        /// The registered name of the destination actor
+        actor_name: String,
+        /// The serialized [RemoteMessage] payload
+        payload: Vec<u8>,
+    },
+
+    /// Notify the receiving node that a remote child it's supervising has
+    /// terminated (or failed) on the sending node
+    Supervision {
+        /// The registered name of the child that went down
+        actor_name: String,
+        /// Whether it terminated abnormally (failed/panic'd) vs. cleanly
+        failed: bool,
+        /// The exit reason, if any
+        reason: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every [WireEnvelope] variant must round-trip through the same
+    /// JSON encoding `node::send_envelope`/`node::recv_envelope` use on the wire.
+    #[test]
+    fn every_variant_round_trips_through_json() {
+        let envelopes = vec![
+            WireEnvelope::Hello {
+                node_name: "node-a".to_string(),
+            },
+            WireEnvelope::Heartbeat,
+            WireEnvelope::Deliver {
+                actor_name: "my_actor".to_string(),
+                payload: vec![1, 2, 3],
+            },
+            WireEnvelope::Supervision {
+                actor_name: "my_actor".to_string(),
+                failed: true,
+                reason: Some("boom".to_string()),
+            },
+        ];
+
+        for envelope in envelopes {
+            let bytes = serde_json::to_vec(&envelope).unwrap();
+            let round_tripped: WireEnvelope = serde_json::from_slice(&bytes).unwrap();
+            // WireEnvelope isn't PartialEq, so compare via their own re-encoding
+            assert_eq!(bytes, serde_json::to_vec(&round_tripped).unwrap());
+        }
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! Remote actors: talking to actors hosted in a different `ractor` process
+//! over a plain TCP connection.
+//!
+//! A [node::NodeServer] owns the connections between this process and its
+//! peers, framing a small [message::WireEnvelope] protocol over each one and
+//! keeping them alive with heartbeats. On top of that, [proxy::expose] makes
+//! a local actor reachable by name from other nodes, and [proxy::watch_remote]
+//! lets local code react to a remote actor's node link dropping. No
+//! [node::NodeServer] sends supervision events for its own actors, so a
+//! remote actor stopping while its link stays up is not reported.
+//!
+//! This is intentionally not wired into the core [crate::Actor]/[crate::ActorRef]
+//! types: a remote peer is addressed explicitly, by the [node::NodeId] of the
+//! connection and the peer's registered [crate::ActorName], rather than
+//! through an `ActorRef` that transparently might be local or remote.
+
+pub mod message;
+pub mod node;
+pub mod proxy;
+
+pub use message::RemoteMessage;
+pub use node::{NodeId, NodeServer, NodeServerMsg};
+pub use proxy::{expose, unexpose, watch_remote};
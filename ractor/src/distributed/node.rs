@@ -0,0 +1,540 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! [NodeServer] is the actor that owns a node's TCP connections to its
+//! peers: it accepts inbound connections, dials outbound ones, frames
+//! [WireEnvelope]s over the wire, sends periodic heartbeats, and reports a
+//! connection as down (via [crate::distributed::proxy::notify_link_down])
+//! once its heartbeat goes quiet or its socket errors out.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+use crate::actor::actor_cell::ActorRef;
+use crate::actor::errors::{MessagingErr, SpawnErr};
+use crate::actor::Actor;
+use crate::distributed::message::{RemoteMessage, WireEnvelope};
+use crate::distributed::proxy;
+use crate::time::TimerHandle;
+use crate::ActorName;
+
+/// Identifies one of this node's connections, stable for the connection's
+/// whole lifetime: the peer's socket address as this node sees it (the
+/// address dialed for an outbound connection, the dialer's address for an
+/// inbound one). Every
+/// message a connection's reader/writer tasks post
+/// ([NodeServerMsg::Inbound]/[NodeServerMsg::LinkDown]) and every entry in
+/// [NodeServerState::connections]/[NodeServerState::last_seen] is keyed by
+/// this, so it's never renamed out from under a task that's mid-flight.
+///
+/// This is *not* generally what calling code should use to target a peer,
+/// though: the acceptor of a connection never learns the dialer's ephemeral
+/// port ahead of time, so it has no way to predict it. Use the node name the
+/// peer announces in its [WireEnvelope::Hello] instead (resolved to a
+/// `NodeId` via [NodeServerState::aliases]) -- see [public_node_id].
+pub type NodeId = String;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a connection can go without *any* inbound frame (a heartbeat
+/// counts, but so does any other envelope) before it's declared down, same
+/// as a socket read error would. Generous relative to [HEARTBEAT_INTERVAL]
+/// so a single delayed heartbeat doesn't trip it.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(3 * 5);
+
+/// Messages a [NodeServer] processes. Application code constructs
+/// [NodeServerMsg::Connect] and [NodeServerMsg::SendRemote] (the latter
+/// usually via [send_remote]); the rest are posted by the listener/connection
+/// tasks and the heartbeat timer [NodeServer] itself sets up.
+pub enum NodeServerMsg {
+    /// Dial out to a peer node listening at `addr`
+    Connect(SocketAddr),
+    /// The listener task accepted an inbound connection from `addr`
+    Accepted(TcpStream, SocketAddr),
+    /// A framed [WireEnvelope] arrived on the connection identified by `NodeId`
+    Inbound(NodeId, WireEnvelope),
+    /// The connection identified by `NodeId` errored out or hung up
+    LinkDown(NodeId),
+    /// Send `payload` (an already-serialized [crate::distributed::message::RemoteMessage])
+    /// to `actor_name` on the node identified by `NodeId`
+    SendRemote {
+        /// Which connection to send over
+        node: NodeId,
+        /// The destination actor's registered name on that node
+        actor_name: String,
+        /// The serialized message payload
+        payload: Vec<u8>,
+    },
+    /// Internal tick, posted every [HEARTBEAT_INTERVAL] via [crate::time::send_interval],
+    /// that fans a [WireEnvelope::Heartbeat] out to every open connection
+    HeartbeatTick,
+}
+
+/// The state backing a single [NodeServer]: this node's own name (sent in
+/// the handshake), the outbound sender half of every connection currently
+/// established, when each one last received *any* inbound frame -- keyed by
+/// [NodeId] -- so [HEARTBEAT_TIMEOUT] can catch a peer that's gone quiet
+/// without its socket actually erroring out, and the node name each
+/// connection's peer has announced (once its [WireEnvelope::Hello] has
+/// arrived), so calling code can target a peer by that name alone.
+pub struct NodeServerState {
+    node_name: String,
+    connections: HashMap<NodeId, Connection>,
+    last_seen: HashMap<NodeId, Instant>,
+    /// Announced peer node name -> the stable [NodeId] of the connection it
+    /// arrived on. Purely additive: a connection's own `NodeId` never
+    /// changes, so this is the only piece of state a `Hello` updates.
+    aliases: HashMap<NodeId, NodeId>,
+    /// Cancels [HEARTBEAT_INTERVAL]'s [NodeServerMsg::HeartbeatTick] timer
+    /// when the actor stops; never read otherwise.
+    _heartbeat: Option<TimerHandle>,
+}
+
+/// Resolve `node` -- either a peer's announced node name or, if no `Hello`
+/// has been received for it yet, its own stable [NodeId] -- to the stable
+/// `NodeId` its connection is actually stored under.
+fn resolve_connection_id(state: &NodeServerState, node: &NodeId) -> NodeId {
+    state
+        .aliases
+        .get(node)
+        .cloned()
+        .unwrap_or_else(|| node.clone())
+}
+
+/// The inverse of [resolve_connection_id]: the name `connection_id`'s peer
+/// has announced, if it's announced one yet, falling back to the stable
+/// `NodeId` itself otherwise. Used when reporting a connection's failure
+/// ([proxy::notify_link_down]) under the identity external `watch_remote`
+/// callers actually registered against.
+fn public_node_id(state: &NodeServerState, connection_id: &NodeId) -> NodeId {
+    state
+        .aliases
+        .iter()
+        .find(|(_, id)| *id == connection_id)
+        .map(|(name, _)| name.clone())
+        .unwrap_or_else(|| connection_id.clone())
+}
+
+/// Remove `connection_id`'s connection, if any, aborting its reader task so
+/// it stops posting [NodeServerMsg::Inbound]/[NodeServerMsg::LinkDown] for a
+/// connection that's already been declared down, and drop any alias that
+/// pointed to it.
+fn teardown_connection(state: &mut NodeServerState, connection_id: &NodeId) {
+    if let Some(conn) = state.connections.remove(connection_id) {
+        conn.reader_task.abort();
+    }
+    state.last_seen.remove(connection_id);
+    state.aliases.retain(|_, id| id != connection_id);
+}
+
+/// The actor managing one node's connections to its peers. Bind it to a
+/// local address to accept inbound connections, dial out with
+/// [NodeServerMsg::Connect], and it keeps every connection alive with
+/// periodic heartbeats, reporting connections as down (firing any
+/// [proxy::watch_remote] callbacks registered against them) once no frame has
+/// arrived for [HEARTBEAT_TIMEOUT] or the socket itself errors out.
+pub struct NodeServer {
+    node_name: String,
+    bind_addr: Option<SocketAddr>,
+}
+
+impl NodeServer {
+    /// Build a node server identifying itself as `node_name`, optionally
+    /// listening on `bind_addr` for inbound connections
+    pub fn new(node_name: String, bind_addr: Option<SocketAddr>) -> Self {
+        Self {
+            node_name,
+            bind_addr,
+        }
+    }
+
+    /// Spawn a [NodeServer], starting its listener (if bound) and heartbeat
+    pub async fn spawn(
+        name: Option<ActorName>,
+        node_name: String,
+        bind_addr: Option<SocketAddr>,
+    ) -> Result<(ActorRef<NodeServer>, tokio::task::JoinHandle<()>), SpawnErr> {
+        Actor::spawn(name, NodeServer::new(node_name, bind_addr)).await
+    }
+}
+
+/// Serialize `msg` and send it to `actor_name` on the node identified by
+/// `node`, via `node_server`'s [NodeServerMsg::SendRemote]. This is the
+/// `send_message`-for-a-remote-actor building block: without it, a caller
+/// has to hand-serialize with `serde_json` and construct
+/// [NodeServerMsg::SendRemote] itself.
+pub fn send_remote<TMsg>(
+    node_server: &ActorRef<NodeServer>,
+    node: NodeId,
+    actor_name: String,
+    msg: &TMsg,
+) -> Result<(), MessagingErr<NodeServerMsg>>
+where
+    TMsg: RemoteMessage,
+{
+    let payload = serde_json::to_vec(msg).map_err(|_| MessagingErr::InvalidActorType)?;
+    node_server.send_message(NodeServerMsg::SendRemote {
+        node,
+        actor_name,
+        payload,
+    })
+}
+
+/// Write `envelope` to `write_half` as a 4-byte big-endian length prefix
+/// followed by its JSON encoding
+async fn send_envelope(
+    write_half: &mut tokio::net::tcp::OwnedWriteHalf,
+    envelope: &WireEnvelope,
+) -> std::io::Result<()> {
+    let bytes = serde_json::to_vec(envelope).map_err(std::io::Error::other)?;
+    write_half.write_all(&(bytes.len() as u32).to_be_bytes()).await?;
+    write_half.write_all(&bytes).await
+}
+
+/// Read one length-prefixed, JSON-encoded [WireEnvelope] from `read_half`
+async fn recv_envelope(read_half: &mut tokio::net::tcp::OwnedReadHalf) -> std::io::Result<WireEnvelope> {
+    let mut len_buf = [0u8; 4];
+    read_half.read_exact(&mut len_buf).await?;
+    let mut body = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+    read_half.read_exact(&mut body).await?;
+    serde_json::from_slice(&body).map_err(std::io::Error::other)
+}
+
+/// One connection's outbound sender plus its reader task, as stored in
+/// [NodeServerState::connections]. The reader task blocks on its own half of
+/// the socket independently of `outbound_tx`, so dropping the sender alone
+/// (which is all that stops the writer task) doesn't stop it -- it has to be
+/// aborted explicitly whenever the connection is torn down, or it keeps
+/// posting [NodeServerMsg::Inbound]/[NodeServerMsg::LinkDown] for a
+/// connection this node has already declared down.
+struct Connection {
+    outbound_tx: mpsc::UnboundedSender<WireEnvelope>,
+    reader_task: tokio::task::JoinHandle<()>,
+}
+
+/// Take ownership of a freshly connected/accepted `stream`, spawn its
+/// reader/writer tasks, send the initial [WireEnvelope::Hello], and return
+/// the [Connection] to register in [NodeServerState::connections] under
+/// `node_id`. `node_id` is fixed for the rest of the connection's life --
+/// every message the reader task posts is tagged with it -- so a later
+/// `Hello` only ever adds an [NodeServerState::aliases] entry, never renames
+/// a live key out from under this task.
+fn spawn_connection(
+    myself: ActorRef<NodeServer>,
+    stream: TcpStream,
+    node_id: NodeId,
+    node_name: String,
+) -> Connection {
+    let (mut read_half, mut write_half) = stream.into_split();
+    let (outbound_tx, mut outbound_rx) = mpsc::unbounded_channel::<WireEnvelope>();
+
+    let _ = outbound_tx.send(WireEnvelope::Hello { node_name });
+
+    tokio::spawn(async move {
+        while let Some(envelope) = outbound_rx.recv().await {
+            if send_envelope(&mut write_half, &envelope).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let reader_myself = myself.clone();
+    let reader_node_id = node_id;
+    let reader_task = tokio::spawn(async move {
+        loop {
+            match recv_envelope(&mut read_half).await {
+                Ok(envelope) => {
+                    if reader_myself
+                        .send_message(NodeServerMsg::Inbound(reader_node_id.clone(), envelope))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(_) => {
+                    let _ = reader_myself.send_message(NodeServerMsg::LinkDown(reader_node_id));
+                    break;
+                }
+            }
+        }
+    });
+
+    Connection {
+        outbound_tx,
+        reader_task,
+    }
+}
+
+#[async_trait::async_trait]
+impl Actor for NodeServer {
+    type Msg = NodeServerMsg;
+    type State = NodeServerState;
+
+    async fn pre_start(&self, myself: ActorRef<Self>) -> Self::State {
+        if let Some(bind_addr) = self.bind_addr {
+            let listener_myself = myself.clone();
+            tokio::spawn(async move {
+                let Ok(listener) = TcpListener::bind(bind_addr).await else {
+                    return;
+                };
+                while let Ok((stream, peer_addr)) = listener.accept().await {
+                    if listener_myself
+                        .send_message(NodeServerMsg::Accepted(stream, peer_addr))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let heartbeat = myself.send_interval(HEARTBEAT_INTERVAL, || NodeServerMsg::HeartbeatTick);
+
+        NodeServerState {
+            node_name: self.node_name.clone(),
+            connections: HashMap::new(),
+            last_seen: HashMap::new(),
+            aliases: HashMap::new(),
+            _heartbeat: Some(heartbeat),
+        }
+    }
+
+    async fn handle(&self, myself: ActorRef<Self>, message: Self::Msg, state: &mut Self::State) {
+        match message {
+            NodeServerMsg::Connect(addr) => {
+                let Ok(stream) = TcpStream::connect(addr).await else {
+                    return;
+                };
+                let node_id = addr.to_string();
+                let conn = spawn_connection(myself, stream, node_id.clone(), state.node_name.clone());
+                state.connections.insert(node_id.clone(), conn);
+                state.last_seen.insert(node_id, Instant::now());
+            }
+            NodeServerMsg::Accepted(stream, peer_addr) => {
+                let node_id = peer_addr.to_string();
+                let conn = spawn_connection(myself, stream, node_id.clone(), state.node_name.clone());
+                state.connections.insert(node_id.clone(), conn);
+                state.last_seen.insert(node_id, Instant::now());
+            }
+            NodeServerMsg::Inbound(node_id, envelope) => {
+                // any inbound frame -- not just a Heartbeat -- counts as a
+                // sign of life for link-failure detection
+                state.last_seen.insert(node_id.clone(), Instant::now());
+                match envelope {
+                    WireEnvelope::Hello { node_name } => {
+                        // Record that `node_name` is reachable over this
+                        // connection, so callers can target it by that name
+                        // via `SendRemote`/`watch_remote` without ever having
+                        // to learn this connection's own `NodeId` -- this is
+                        // purely additive, unlike renaming `node_id` itself,
+                        // so it can never race the reader task that's still
+                        // tagging messages with the unchanged `node_id`.
+                        state.aliases.insert(node_name, node_id);
+                    }
+                    WireEnvelope::Heartbeat => {}
+                    WireEnvelope::Deliver { actor_name, payload } => {
+                        let _ = proxy::deliver_local(&actor_name, &payload);
+                    }
+                    WireEnvelope::Supervision {
+                        actor_name,
+                        failed,
+                        reason,
+                    } => {
+                        let public_id = public_node_id(state, &node_id);
+                        proxy::notify_remote_supervision(&public_id, &actor_name, failed, reason);
+                    }
+                }
+            }
+            NodeServerMsg::LinkDown(node_id) => {
+                let public_id = public_node_id(state, &node_id);
+                teardown_connection(state, &node_id);
+                proxy::notify_link_down(&public_id);
+            }
+            NodeServerMsg::SendRemote {
+                node,
+                actor_name,
+                payload,
+            } => {
+                let connection_id = resolve_connection_id(state, &node);
+                if let Some(conn) = state.connections.get(&connection_id) {
+                    let _ = conn.outbound_tx.send(WireEnvelope::Deliver { actor_name, payload });
+                }
+            }
+            NodeServerMsg::HeartbeatTick => {
+                for conn in state.connections.values() {
+                    let _ = conn.outbound_tx.send(WireEnvelope::Heartbeat);
+                }
+
+                let now = Instant::now();
+                let timed_out: Vec<NodeId> = state
+                    .last_seen
+                    .iter()
+                    .filter(|(_, seen)| now.duration_since(**seen) > HEARTBEAT_TIMEOUT)
+                    .map(|(node_id, _)| node_id.clone())
+                    .collect();
+                for node_id in timed_out {
+                    let public_id = public_node_id(state, &node_id);
+                    teardown_connection(state, &node_id);
+                    proxy::notify_link_down(&public_id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::actor_cell::ActorRef;
+    use crate::Actor;
+    use serde::{Deserialize, Serialize};
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Ping(u32);
+
+    #[derive(Clone)]
+    struct PingCollector {
+        received: Arc<StdMutex<Vec<u32>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Actor for PingCollector {
+        type Msg = Ping;
+        type State = ();
+
+        async fn pre_start(&self, _myself: ActorRef<Self>) -> Self::State {}
+
+        async fn handle(&self, _myself: ActorRef<Self>, message: Self::Msg, _state: &mut Self::State) {
+            self.received.lock().unwrap().push(message.0);
+        }
+    }
+
+    async fn wait_until(mut check: impl FnMut() -> bool, attempts: u32) -> bool {
+        for _ in 0..attempts {
+            if check() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        false
+    }
+
+    /// End-to-end test over real loopback TCP sockets (not just the
+    /// pure-logic pieces covered in message.rs/proxy.rs): two [NodeServer]s
+    /// connect, exchange a [WireEnvelope::Deliver] addressed by the
+    /// *accepting* side's announced node name (exercising the Hello-driven
+    /// [NodeServerState::aliases] entry, not just the dialer's
+    /// address-derived id), survive past a real [HEARTBEAT_INTERVAL] tick
+    /// without being declared down, and then correctly report
+    /// [NodeServerMsg::LinkDown] once the connection actually drops.
+    #[tokio::test]
+    async fn two_node_servers_exchange_messages_and_detect_link_down_over_loopback() {
+        let bind_addr: SocketAddr = "127.0.0.1:28733".parse().unwrap();
+
+        let (node_a, _handle_a) = NodeServer::spawn(None, "node-a".to_string(), Some(bind_addr))
+            .await
+            .unwrap();
+        let (node_b, _handle_b) = NodeServer::spawn(None, "node-b".to_string(), None)
+            .await
+            .unwrap();
+
+        let received = Arc::new(StdMutex::new(vec![]));
+        let (collector, _collector_handle) = PingCollector::spawn(
+            None,
+            PingCollector {
+                received: received.clone(),
+            },
+        )
+        .await
+        .unwrap();
+        proxy::expose::<Ping>("node_test_collector", collector.get_cell());
+
+        node_b.send_message(NodeServerMsg::Connect(bind_addr)).unwrap();
+
+        // Keep retrying `send_remote` addressed to "node-a" -- the name
+        // node_a announces in its Hello -- rather than the bind address:
+        // node_b only learns that name via the handshake, so this only
+        // succeeds once `handle` has recorded the alias.
+        let delivered = wait_until(
+            || {
+                let ping = Ping(7);
+                let _ = send_remote(
+                    &node_b,
+                    "node-a".to_string(),
+                    "node_test_collector".to_string(),
+                    &ping,
+                );
+                !received.lock().unwrap().is_empty()
+            },
+            100,
+        )
+        .await;
+        assert!(delivered, "Deliver envelope never reached the exposed actor");
+        assert_eq!(*received.lock().unwrap(), vec![7]);
+
+        // The connection should survive a real heartbeat tick without being
+        // torn down: it's well under HEARTBEAT_TIMEOUT, so node_b should
+        // still be able to reach node_a (proven by a second ping arriving --
+        // if node_b had declared the link down, its `state.connections` would
+        // no longer hold an entry for it and this send would no-op forever
+        // instead of being delivered).
+        tokio::time::sleep(HEARTBEAT_INTERVAL + Duration::from_millis(500)).await;
+        let ping_after_heartbeat = wait_until(
+            || {
+                let ping = Ping(9);
+                let _ = send_remote(
+                    &node_b,
+                    "node-a".to_string(),
+                    "node_test_collector".to_string(),
+                    &ping,
+                );
+                received.lock().unwrap().contains(&9)
+            },
+            100,
+        )
+        .await;
+        assert!(
+            ping_after_heartbeat,
+            "connection should still be up after a heartbeat interval"
+        );
+
+        proxy::unexpose("node_test_collector");
+
+        // Now actually sever a connection and confirm node_a notices via its
+        // own socket read erroring out, not via a synthetic message. Dial
+        // node_a directly with a bare `TcpStream` (sidestepping `node_b`,
+        // whose background reader/writer tasks for its own connection aren't
+        // tied to its actor's lifecycle, so killing it wouldn't close its
+        // socket) and drop it once node_a has had a chance to accept and
+        // register it.
+        let raw_peer = TcpStream::connect(bind_addr).await.unwrap();
+        let raw_peer_id = raw_peer.local_addr().unwrap().to_string();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let link_down_seen = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let link_down_seen_cb = link_down_seen.clone();
+        proxy::watch_remote(raw_peer_id, "node_test_raw_peer".to_string(), move |failed, _reason| {
+            if failed {
+                link_down_seen_cb.store(true, std::sync::atomic::Ordering::SeqCst);
+            }
+        });
+
+        drop(raw_peer);
+
+        let saw_link_down = wait_until(
+            || link_down_seen.load(std::sync::atomic::Ordering::SeqCst),
+            250,
+        )
+        .await;
+        assert!(saw_link_down, "node_a never reported the dropped connection as down");
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! The local side of remote actor delivery: a registry mapping an
+//! [ActorName] to the local [ActorCell] it should deliver to plus the
+//! decoder for whatever [RemoteMessage] type that actor expects, and a
+//! parallel registry of local actors that want to hear about a *remote*
+//! actor's supervision events.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::actor::actor_cell::ActorCell;
+use crate::distributed::message::RemoteMessage;
+use crate::distributed::node::NodeId;
+use crate::ActorName;
+
+type Decoder = Box<dyn Fn(&[u8]) -> Option<Box<dyn Any + Send>> + Send + Sync>;
+
+struct ExposedEntry {
+    cell: ActorCell,
+    decode: Decoder,
+}
+
+fn exposed() -> &'static Mutex<HashMap<ActorName, ExposedEntry>> {
+    static EXPOSED: OnceLock<Mutex<HashMap<&'static str, ExposedEntry>>> = OnceLock::new();
+    EXPOSED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Make `cell` reachable by name from other nodes: an incoming
+/// `Deliver { actor_name, .. }` envelope addressed to `name` is decoded as
+/// `TMsg` and forwarded onto `cell`'s message port, exactly as if it had
+/// been a local [crate::ActorRef::send_message] call.
+pub fn expose<TMsg>(name: ActorName, cell: ActorCell)
+where
+    TMsg: RemoteMessage,
+{
+    let decode: Decoder = Box::new(|bytes: &[u8]| -> Option<Box<dyn Any + Send>> {
+        serde_json::from_slice::<TMsg>(bytes)
+            .ok()
+            .map(|msg| Box::new(msg) as Box<dyn Any + Send>)
+    });
+    exposed()
+        .lock()
+        .unwrap()
+        .insert(name, ExposedEntry { cell, decode });
+}
+
+/// Stop `name` from being remotely reachable
+pub fn unexpose(name: ActorName) {
+    exposed().lock().unwrap().remove(name);
+}
+
+/// Decode `payload` per whatever was registered for `actor_name` via
+/// [expose], and deliver it onto that actor's message port. Returns `Err`
+/// if `actor_name` isn't exposed, or the payload doesn't decode as the type
+/// it was exposed with.
+pub(crate) fn deliver_local(actor_name: &str, payload: &[u8]) -> Result<(), ()> {
+    let guard = exposed().lock().unwrap();
+    let entry = guard.get(actor_name).ok_or(())?;
+    let boxed = (entry.decode)(payload).ok_or(())?;
+    entry.cell.send_boxed_message(boxed).map_err(|_| ())
+}
+
+type RemoteSupervisionCallback = Box<dyn Fn(bool, Option<String>) + Send + Sync>;
+
+fn remote_supervisors() -> &'static Mutex<HashMap<(NodeId, String), Vec<RemoteSupervisionCallback>>> {
+    static SUPERVISORS: OnceLock<Mutex<HashMap<(NodeId, String), Vec<RemoteSupervisionCallback>>>> =
+        OnceLock::new();
+    SUPERVISORS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Register interest in the remote actor `actor_name` on `node`'s
+/// supervision events: `on_event(failed, reason)` is invoked whenever that
+/// node reports (via [super::message::WireEnvelope::Supervision]) that the
+/// actor terminated, or whenever the node link itself drops (`failed =
+/// true`, since an unreachable supervisor can't be distinguished from a
+/// dead one). A [super::node::NodeServer] never sends a `Supervision`
+/// envelope when one of its own actors stops, so between two `NodeServer`s
+/// only a dropped link is reported.
+pub fn watch_remote(node: NodeId, actor_name: String, on_event: impl Fn(bool, Option<String>) + Send + Sync + 'static) {
+    remote_supervisors()
+        .lock()
+        .unwrap()
+        .entry((node, actor_name))
+        .or_default()
+        .push(Box::new(on_event));
+}
+
+/// Fire every callback registered via [watch_remote] for `(node, actor_name)`
+pub(crate) fn notify_remote_supervision(node: &NodeId, actor_name: &str, failed: bool, reason: Option<String>) {
+    if let Some(callbacks) = remote_supervisors()
+        .lock()
+        .unwrap()
+        .get(&(node.clone(), actor_name.to_string()))
+    {
+        for callback in callbacks {
+            callback(failed, reason.clone());
+        }
+    }
+}
+
+/// Fire every callback registered for any actor on `node`, as `failed` --
+/// called when the node's link itself drops, since that's indistinguishable
+/// from every actor on it having failed.
+pub(crate) fn notify_link_down(node: &NodeId) {
+    let guard = remote_supervisors().lock().unwrap();
+    for ((n, _), callbacks) in guard.iter() {
+        if n == node {
+            for callback in callbacks {
+                callback(true, Some("node link down".to_string()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::actor_cell::ActorRef;
+    use crate::Actor;
+    use serde::{Deserialize, Serialize};
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct Greeting(String);
+
+    #[derive(Clone)]
+    struct Greeter {
+        received: Arc<StdMutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Actor for Greeter {
+        type Msg = Greeting;
+        type State = ();
+
+        async fn pre_start(&self, _myself: ActorRef<Self>) -> Self::State {}
+
+        async fn handle(&self, _myself: ActorRef<Self>, message: Self::Msg, _state: &mut Self::State) {
+            self.received.lock().unwrap().push(message.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn deliver_local_decodes_and_forwards_to_the_exposed_actor() {
+        let received = Arc::new(StdMutex::new(vec![]));
+        let (greeter, _handle) = Greeter::spawn(
+            None,
+            Greeter {
+                received: received.clone(),
+            },
+        )
+        .await
+        .unwrap();
+
+        expose::<Greeting>("proxy_test_greeter", greeter.get_cell());
+
+        let payload = serde_json::to_vec(&Greeting("hi".to_string())).unwrap();
+        assert!(deliver_local("proxy_test_greeter", &payload).is_ok());
+
+        for _ in 0..500 {
+            if !received.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert_eq!(*received.lock().unwrap(), vec!["hi".to_string()]);
+
+        unexpose("proxy_test_greeter");
+    }
+
+    #[test]
+    fn deliver_local_rejects_an_unexposed_name() {
+        assert!(deliver_local("proxy_test_never_exposed", &[]).is_err());
+    }
+
+    #[test]
+    fn notify_link_down_fires_every_watcher_for_that_node() {
+        let node: NodeId = "proxy_test_node".to_string();
+        let seen = Arc::new(StdMutex::new(vec![]));
+
+        let seen_a = seen.clone();
+        watch_remote(node.clone(), "actor_a".to_string(), move |failed, _reason| {
+            seen_a.lock().unwrap().push(("actor_a", failed));
+        });
+        let seen_b = seen.clone();
+        watch_remote(node.clone(), "actor_b".to_string(), move |failed, _reason| {
+            seen_b.lock().unwrap().push(("actor_b", failed));
+        });
+
+        notify_link_down(&node);
+
+        let mut fired = seen.lock().unwrap().clone();
+        fired.sort();
+        assert_eq!(fired, vec![("actor_a", true), ("actor_b", true)]);
+    }
+}
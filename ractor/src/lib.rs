@@ -106,8 +106,11 @@
 //! Actors in `ractor` also support supervision. This is done by "linking" actors together in a supervisor-child relationship.
 //! A supervisor is "responsible" for the child actor, and as such is notified when the actor starts, stops, and fails (panics).
 //!
-//! Supervision is presently left to the implementor, but you can see a suite of supervision tests in `crate::actor::tests::supervisor`
-//! for examples on the supported functionality.
+//! On top of that linking, [crate::actor::supervisor::Supervisor] provides an Erlang/OTP-style supervisor behaviour: declare
+//! a set of [crate::actor::supervisor::ChildSpec]s and a restart [crate::actor::supervisor::Strategy] (`OneForOne`, `OneForAll`,
+//! or `RestForOne`), and the supervisor restarts children per their [crate::actor::supervisor::RestartPolicy] whenever one
+//! terminates. If restarts happen faster than the configured [crate::actor::supervisor::RestartIntensity] allows, the
+//! supervisor gives up and escalates the failure to its own supervisor, rather than restarting forever.
 //!
 //! NOTE: panic's in `pre_start` of an actor will cause failures to spawn, rather than supervision notified failurs as the actor hasn't "linked"
 //! to its supervisor yet. However failures in `post_start`, `handle`, `handle_supervisor_evt`, `post_stop` will notify the supervisor should a failure
@@ -127,6 +130,13 @@
 //! are how an actor's supervisor(s) are notified of events of their children and can handle lifetime events for them.
 //! 4. Messages: Regular, user-defined, messages are the last channel of communication to actors. They are the lowest priority of the 4 message types and denote general actor work. The first
 //! 3 messages types (signals, stop, supervision) are generally quiet unless it's a lifecycle event for the actor, but this channel is the "work" channel doing what your actor wants to do!
+//!
+//! ## Distributed actors
+//!
+//! With the `remote` feature enabled, [distributed] lets a process talk to actors hosted by a different `ractor` process
+//! over a plain TCP connection. A [distributed::node::NodeServer] owns the connections to this node's peers, and
+//! [distributed::expose]/[distributed::watch_remote] make a local actor reachable by name from, respectively report on
+//! the supervision status of, an actor over on the other side of one of those connections.
 
 #![deny(warnings)]
 #![warn(unused_imports)]
@@ -156,15 +166,17 @@ use criterion as _;
 #[cfg(test)]
 use rand as _;
 
-// WIP
-// #[cfg(feature = "remote")]
-// pub mod distributed;
+#[cfg(feature = "remote")]
+pub mod distributed;
 
 // re-exports
 pub use actor::actor_cell::{ActorCell, ActorRef, ActorStatus, ACTIVE_STATES};
 pub use actor::errors::{ActorErr, MessagingErr, SpawnErr};
 pub use actor::messages::{Signal, SupervisionEvent};
-pub use actor::{Actor, ActorRuntime};
+pub use actor::supervisor::{
+    ChildFactory, ChildSpec, RestartIntensity, RestartPolicy, Strategy, Supervisor,
+};
+pub use actor::{Actor, ActorRuntime, ExecutionMode};
 pub use actor_id::ActorId;
 pub use port::{OutputMessage, OutputPort, RpcReplyPort};
 
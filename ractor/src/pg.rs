@@ -0,0 +1,185 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! Process groups: a process-global, many-to-many membership registry,
+//! equivalent to Erlang's `pg` module. An actor can join any number of
+//! named groups, and any caller can ask who's currently a member.
+//!
+//! [subscribe]/[publish] build a typed MPMC publish-subscribe fabric on top
+//! of that membership: publishing clones a message to every current member
+//! of a topic (which is just a [GroupName]), and a subscriber is dropped
+//! from the topic automatically once it terminates, since every actor
+//! prunes itself from all of its groups on exit (see [prune]).
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::actor::actor_cell::ActorCell;
+use crate::{GroupName, Message};
+
+fn groups() -> &'static Mutex<HashMap<GroupName, Vec<ActorCell>>> {
+    static GROUPS: OnceLock<Mutex<HashMap<&'static str, Vec<ActorCell>>>> = OnceLock::new();
+    GROUPS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Join `actor` to `group`. A no-op if it's already a member.
+pub fn join(group: GroupName, actor: ActorCell) {
+    let mut guard = groups().lock().unwrap();
+    let members = guard.entry(group).or_default();
+    if !members.contains(&actor) {
+        members.push(actor);
+    }
+}
+
+/// Remove `actor` from `group`
+pub fn leave(group: GroupName, actor: &ActorCell) {
+    if let Some(members) = groups().lock().unwrap().get_mut(group) {
+        members.retain(|m| m != actor);
+    }
+}
+
+/// The current members of `group`
+pub fn get_members(group: GroupName) -> Vec<ActorCell> {
+    groups()
+        .lock()
+        .unwrap()
+        .get(group)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Remove `actor` from every group it currently belongs to. Called when an
+/// actor terminates so dead actors don't linger as group members.
+pub(crate) fn prune(actor: &ActorCell) {
+    let mut guard = groups().lock().unwrap();
+    for members in guard.values_mut() {
+        members.retain(|m| m != actor);
+    }
+}
+
+/// Subscribe `actor` to `topic`, so it receives every message [published][publish]
+/// to that topic from now on. Equivalent to [join], but named for the
+/// publish-subscribe use case; `actor`'s own message type must be (or accept,
+/// via an enum variant) `M`, since a mismatched message is just silently
+/// dropped by the actor's message loop.
+pub fn subscribe<M>(topic: GroupName, actor: ActorCell)
+where
+    M: Message + Clone,
+{
+    join(topic, actor);
+}
+
+/// Unsubscribe `actor` from `topic`
+pub fn unsubscribe(topic: GroupName, actor: &ActorCell) {
+    leave(topic, actor);
+}
+
+/// Publish `msg` to every current subscriber of `topic`, cloning it once per
+/// subscriber. Subscribers which have since terminated are never delivered
+/// to, since they're pruned from every topic as part of shutting down.
+pub fn publish<M>(topic: GroupName, msg: M)
+where
+    M: Message + Clone,
+{
+    for subscriber in get_members(topic) {
+        let _ = subscriber.send_boxed_message(Box::new(msg.clone()));
+    }
+}
+
+/// The current subscribers of `topic`
+pub fn subscribers(topic: GroupName) -> Vec<ActorCell> {
+    get_members(topic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::actor_cell::ActorRef;
+    use crate::Actor;
+    use std::sync::{Arc, Mutex as StdMutex};
+    use std::time::Duration;
+
+    #[derive(Clone)]
+    struct Collector {
+        received: Arc<StdMutex<Vec<u32>>>,
+    }
+
+    #[derive(Clone)]
+    struct Event(u32);
+
+    #[async_trait::async_trait]
+    impl Actor for Collector {
+        type Msg = Event;
+        type State = ();
+
+        async fn pre_start(&self, _myself: ActorRef<Self>) -> Self::State {}
+
+        async fn handle(&self, _myself: ActorRef<Self>, message: Self::Msg, _state: &mut Self::State) {
+            self.received.lock().unwrap().push(message.0);
+        }
+    }
+
+    async fn wait_until(mut check: impl FnMut() -> bool) {
+        for _ in 0..500 {
+            if check() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("condition never became true");
+    }
+
+    #[tokio::test]
+    async fn publish_delivers_to_every_subscriber() {
+        let topic = "pg_test_publish_delivers_to_every_subscriber";
+        let received_a = Arc::new(StdMutex::new(vec![]));
+        let received_b = Arc::new(StdMutex::new(vec![]));
+        let (a, _handle_a) = Collector::spawn(
+            None,
+            Collector {
+                received: received_a.clone(),
+            },
+        )
+        .await
+        .unwrap();
+        let (b, _handle_b) = Collector::spawn(
+            None,
+            Collector {
+                received: received_b.clone(),
+            },
+        )
+        .await
+        .unwrap();
+
+        subscribe::<Event>(topic, a.get_cell());
+        subscribe::<Event>(topic, b.get_cell());
+        publish(topic, Event(7));
+
+        wait_until(|| received_a.lock().unwrap().len() == 1 && received_b.lock().unwrap().len() == 1).await;
+        assert_eq!(*received_a.lock().unwrap(), vec![7]);
+        assert_eq!(*received_b.lock().unwrap(), vec![7]);
+    }
+
+    #[tokio::test]
+    async fn a_terminated_actor_is_pruned_from_its_groups() {
+        let topic = "pg_test_prune_on_death";
+        let (a, handle) = Collector::spawn(
+            None,
+            Collector {
+                received: Arc::new(StdMutex::new(vec![])),
+            },
+        )
+        .await
+        .unwrap();
+
+        join(topic, a.get_cell());
+        assert_eq!(get_members(topic).len(), 1);
+
+        a.stop(None);
+        handle.await.unwrap();
+
+        assert!(get_members(topic).is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! Ports are the primitives actors use to communicate with the outside world:
+//! [RpcReplyPort] for a single reply to a request, and [OutputPort] for
+//! fan-out publication to any number of listeners.
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use crate::Message;
+
+/// A one-shot reply channel, handed to an actor alongside a request so it can
+/// send a single reply back to the caller. Mirrors [tokio::sync::oneshot::Sender]
+/// but additionally tracks an optional timeout so callers can tell whether it's
+/// still worth sending on.
+pub struct RpcReplyPort<T> {
+    port: oneshot::Sender<T>,
+    timeout: Option<Duration>,
+}
+
+impl<T> RpcReplyPort<T> {
+    /// Send the reply. Consumes the port, as only a single reply is supported.
+    pub fn send(self, value: T) -> Result<(), T> {
+        self.port.send(value)
+    }
+
+    /// Check if the receiving side has already dropped (i.e. the caller gave up,
+    /// most often because its own timeout or cancellation fired). Actors should
+    /// check this before doing expensive work to compute a reply nobody wants.
+    pub fn is_closed(&self) -> bool {
+        self.port.is_closed()
+    }
+
+    /// The timeout which was attached to this reply port, if any
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+
+impl<T> From<(oneshot::Sender<T>, Option<Duration>)> for RpcReplyPort<T> {
+    fn from(value: (oneshot::Sender<T>, Option<Duration>)) -> Self {
+        Self {
+            port: value.0,
+            timeout: value.1,
+        }
+    }
+}
+
+impl<T> Debug for RpcReplyPort<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.timeout {
+            Some(duration) => write!(f, "RpcReplyPort<with timeout {duration:?}>"),
+            None => write!(f, "RpcReplyPort<no timeout>"),
+        }
+    }
+}
+
+/// A message published over an [OutputPort]. Output ports clone the message to
+/// each subscriber, so payloads need to be cheaply cloneable.
+pub trait OutputMessage: Message + Clone {}
+impl<T: Message + Clone> OutputMessage for T {}
+
+type OutputPortSubscriber<TMsg> = Box<dyn Fn(TMsg) + Send + Sync + 'static>;
+
+/// A broadcast channel which an actor can publish arbitrary events on, and
+/// which any number of outside listeners can subscribe to. Used for fan-out
+/// notifications that aren't tied to a specific request/response exchange.
+pub struct OutputPort<TMsg>
+where
+    TMsg: OutputMessage,
+{
+    subscribers: Mutex<Vec<OutputPortSubscriber<TMsg>>>,
+}
+
+impl<TMsg> Default for OutputPort<TMsg>
+where
+    TMsg: OutputMessage,
+{
+    fn default() -> Self {
+        Self {
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl<TMsg> OutputPort<TMsg>
+where
+    TMsg: OutputMessage,
+{
+    /// Subscribe a new listener to this output port
+    pub fn subscribe<F>(&self, listener: F)
+    where
+        F: Fn(TMsg) + Send + Sync + 'static,
+    {
+        self.subscribers.lock().unwrap().push(Box::new(listener));
+    }
+
+    /// Publish a message to all current subscribers
+    pub fn send(&self, msg: TMsg) {
+        for subscriber in self.subscribers.lock().unwrap().iter() {
+            subscriber(msg.clone());
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! A process-global registry mapping [crate::ActorName]s to the [ActorCell]
+//! of the actor currently holding that name, equivalent to Erlang's
+//! registered process names.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::actor::actor_cell::ActorCell;
+use crate::ActorName;
+
+fn registry() -> &'static Mutex<HashMap<ActorName, ActorCell>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<&'static str, ActorCell>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Register `cell` under `name`. Fails if the name is already claimed by a
+/// still-active actor.
+pub(crate) fn register(name: ActorName, cell: ActorCell) -> Result<(), String> {
+    let mut guard = registry().lock().unwrap();
+    if let Some(existing) = guard.get(name) {
+        if existing.is_active() {
+            return Err(format!("Name '{name}' is already registered"));
+        }
+    }
+    guard.insert(name, cell);
+    Ok(())
+}
+
+/// Remove `name` from the registry, generally called when its actor stops
+pub(crate) fn unregister(name: ActorName) {
+    registry().lock().unwrap().remove(name);
+}
+
+/// Look up an actor by its registered name
+pub fn where_is(name: ActorName) -> Option<ActorCell> {
+    registry().lock().unwrap().get(name).cloned()
+}
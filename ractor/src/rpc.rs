@@ -0,0 +1,337 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! Request/response helpers layered on top of an actor's plain message port
+//! and [RpcReplyPort].
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::sync::oneshot;
+use tokio::time::Duration;
+
+use crate::actor::actor_cell::ActorRef;
+use crate::actor::errors::MessagingErr;
+use crate::actor::Actor;
+use crate::time::Clock;
+use crate::RpcReplyPort;
+
+/// Race `fut` against `duration` elapsing on `clock`, `biased` towards `fut`
+/// so a reply that's already ready wins over a timeout that fires on the
+/// same poll. Returns `None` if the timeout wins.
+async fn race_with_clock<T>(clock: &Arc<dyn Clock>, duration: Duration, fut: impl Future<Output = T>) -> Option<T> {
+    tokio::select! {
+        biased;
+        res = fut => Some(res),
+        _ = clock.sleep(duration) => None,
+    }
+}
+
+/// Send a message to `actor` built from a [RpcReplyPort], and await the reply.
+/// Returns `Ok(None)` if `timeout` elapses (or the actor drops the reply port)
+/// before a reply arrives. This is the building block behind [ActorRef::call].
+/// Timeouts are measured against the
+/// process-wide real clock; see [call_with_clock] to drive them from a
+/// [crate::time::MockClock] in tests.
+pub async fn call<TActor, TReply, F>(
+    actor: &ActorRef<TActor>,
+    msg_builder: F,
+    timeout: Option<Duration>,
+) -> Result<Option<TReply>, MessagingErr<TActor::Msg>>
+where
+    TActor: Actor,
+    F: FnOnce(RpcReplyPort<TReply>) -> TActor::Msg,
+{
+    call_with_clock(crate::time::clock::real_clock(), actor, msg_builder, timeout).await
+}
+
+/// Like [call], but measuring `timeout` against an explicit [Clock] rather
+/// than the real one, so a test can drive it with a [crate::time::MockClock].
+pub async fn call_with_clock<TActor, TReply, F>(
+    clock: Arc<dyn Clock>,
+    actor: &ActorRef<TActor>,
+    msg_builder: F,
+    timeout: Option<Duration>,
+) -> Result<Option<TReply>, MessagingErr<TActor::Msg>>
+where
+    TActor: Actor,
+    F: FnOnce(RpcReplyPort<TReply>) -> TActor::Msg,
+{
+    let (tx, rx) = oneshot::channel();
+    let port: RpcReplyPort<TReply> = (tx, timeout).into();
+    let msg = msg_builder(port);
+
+    actor
+        .send_message(msg)
+        .map_err(|_| MessagingErr::ChannelClosed)?;
+
+    match timeout {
+        Some(duration) => match race_with_clock(&clock, duration, rx).await {
+            Some(Ok(reply)) => Ok(Some(reply)),
+            Some(Err(_)) | None => Ok(None),
+        },
+        None => Ok(rx.await.ok()),
+    }
+}
+
+/// The outcome of a [call_with_retries], distinguishing the ways a retried
+/// call can fail to produce a reply from the single success case.
+#[derive(Debug)]
+pub enum CallResult<TReply> {
+    /// A reply arrived within `attempts` tries
+    Success(TReply),
+    /// Every attempt timed out without a reply
+    Timeout,
+    /// The caller tripped the [CancellationToken] before a reply arrived
+    Cancelled,
+    /// The actor's message port is closed; it has already stopped
+    ActorGone,
+}
+
+struct CancellationInner {
+    cancelled: std::sync::atomic::AtomicBool,
+    notify: tokio::sync::Notify,
+}
+
+/// A handle the caller of [call_with_retries] can use to abort an in-flight
+/// request early. Tripping it closes the current attempt's [RpcReplyPort],
+/// so a callee that checks [RpcReplyPort::is_closed] can short-circuit work
+/// it's no longer worth doing.
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<CancellationInner>,
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(CancellationInner {
+                cancelled: std::sync::atomic::AtomicBool::new(false),
+                notify: tokio::sync::Notify::new(),
+            }),
+        }
+    }
+}
+
+impl CancellationToken {
+    /// Create a new, not-yet-cancelled token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trip the token, aborting any in-flight [call_with_retries] attempt
+    /// that's watching it
+    pub fn cancel(&self) {
+        self.inner
+            .cancelled
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Whether [CancellationToken::cancel] has already been called
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    async fn cancelled(&self) {
+        loop {
+            if self.is_cancelled() {
+                return;
+            }
+            self.inner.notify.notified().await;
+        }
+    }
+}
+
+/// Like [call], but re-issues the request up to `attempts` times if an
+/// individual attempt times out, and can be aborted early via `cancellation`.
+/// A closed actor message port is reported as [CallResult::ActorGone]
+/// rather than as a timeout, and cancellation is distinguished from
+/// exhausting all retries. Per-attempt
+/// timeouts are measured against the process-wide real clock; see
+/// [call_with_retries_with_clock] to drive them from a [crate::time::MockClock]
+/// in tests.
+pub async fn call_with_retries<TActor, TReply, F>(
+    actor: &ActorRef<TActor>,
+    msg_builder: F,
+    attempts: usize,
+    per_attempt_timeout: Duration,
+    cancellation: &CancellationToken,
+) -> CallResult<TReply>
+where
+    TActor: Actor,
+    F: Fn(RpcReplyPort<TReply>) -> TActor::Msg,
+{
+    call_with_retries_with_clock(
+        crate::time::clock::real_clock(),
+        actor,
+        msg_builder,
+        attempts,
+        per_attempt_timeout,
+        cancellation,
+    )
+    .await
+}
+
+/// Like [call_with_retries], but measuring each attempt's timeout against an
+/// explicit [Clock] rather than the real one, so a test can drive it with a
+/// [crate::time::MockClock].
+pub async fn call_with_retries_with_clock<TActor, TReply, F>(
+    clock: Arc<dyn Clock>,
+    actor: &ActorRef<TActor>,
+    msg_builder: F,
+    attempts: usize,
+    per_attempt_timeout: Duration,
+    cancellation: &CancellationToken,
+) -> CallResult<TReply>
+where
+    TActor: Actor,
+    F: Fn(RpcReplyPort<TReply>) -> TActor::Msg,
+{
+    for attempt in 1..=attempts.max(1) {
+        if cancellation.is_cancelled() {
+            return CallResult::Cancelled;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let port: RpcReplyPort<TReply> = (tx, Some(per_attempt_timeout)).into();
+        let msg = msg_builder(port);
+
+        if actor.send_message(msg).is_err() {
+            return CallResult::ActorGone;
+        }
+
+        tokio::select! {
+            biased;
+
+            _ = cancellation.cancelled() => return CallResult::Cancelled,
+            reply = race_with_clock(&clock, per_attempt_timeout, rx) => {
+                match reply {
+                    Some(Ok(reply)) => return CallResult::Success(reply),
+                    // reply port dropped without a send: treat like a timeout for this attempt
+                    _ if attempt < attempts.max(1) => continue,
+                    _ => return CallResult::Timeout,
+                }
+            }
+        }
+    }
+    CallResult::Timeout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::actor_cell::ActorRef;
+    use crate::time::MockClock;
+    use crate::Actor;
+
+    #[derive(Clone)]
+    struct Echo;
+
+    enum EchoMsg {
+        Reply(RpcReplyPort<u32>),
+        /// Holds onto the reply port without ever sending, so a caller's
+        /// per-attempt timeout is the only thing that can resolve the call
+        Stall(RpcReplyPort<u32>),
+    }
+
+    #[async_trait::async_trait]
+    impl Actor for Echo {
+        type Msg = EchoMsg;
+        // ports from `Stall` messages are parked here rather than dropped, so
+        // the channel stays open and a call can only resolve via its own
+        // per-attempt timeout, not via the port closing early
+        type State = Vec<RpcReplyPort<u32>>;
+
+        async fn pre_start(&self, _myself: ActorRef<Self>) -> Self::State {
+            Vec::new()
+        }
+
+        async fn handle(&self, _myself: ActorRef<Self>, message: Self::Msg, state: &mut Self::State) {
+            match message {
+                EchoMsg::Reply(port) => {
+                    let _ = port.send(42);
+                }
+                EchoMsg::Stall(port) => state.push(port),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn call_with_retries_succeeds_on_first_attempt() {
+        let (echo, _handle) = Echo::spawn(None, Echo).await.unwrap();
+        let result = echo
+            .call_with_retries(
+                EchoMsg::Reply,
+                3,
+                Duration::from_secs(1),
+                &CancellationToken::new(),
+            )
+            .await;
+        assert!(matches!(result, CallResult::Success(42)));
+    }
+
+    #[tokio::test]
+    async fn call_with_retries_times_out_after_exhausting_attempts() {
+        let (echo, _handle) = Echo::spawn(None, Echo).await.unwrap();
+        let clock = MockClock::new();
+        let clock_for_call: Arc<dyn Clock> = Arc::new(clock.clone());
+
+        // run the call on its own task so this test can drive the mock clock
+        // from the outside while it's in flight, advancing past each
+        // per-attempt timeout in turn (the stalling actor never replies)
+        let call_task = tokio::spawn(async move {
+            call_with_retries_with_clock(
+                clock_for_call,
+                &echo,
+                EchoMsg::Stall,
+                2,
+                Duration::from_secs(1),
+                &CancellationToken::new(),
+            )
+            .await
+        });
+
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(1)).await;
+        clock.advance(Duration::from_secs(1)).await;
+
+        assert!(matches!(call_task.await.unwrap(), CallResult::Timeout));
+    }
+
+    #[tokio::test]
+    async fn call_with_retries_reports_actor_gone() {
+        let (echo, handle) = Echo::spawn(None, Echo).await.unwrap();
+        echo.stop(None);
+        handle.await.unwrap();
+
+        let result = echo
+            .call_with_retries(
+                EchoMsg::Reply,
+                3,
+                Duration::from_secs(1),
+                &CancellationToken::new(),
+            )
+            .await;
+        assert!(matches!(result, CallResult::ActorGone));
+    }
+
+    #[tokio::test]
+    async fn call_with_retries_reports_cancellation() {
+        let (echo, _handle) = Echo::spawn(None, Echo).await.unwrap();
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+
+        let result = echo
+            .call_with_retries(
+                EchoMsg::Reply,
+                3,
+                Duration::from_secs(1),
+                &cancellation,
+            )
+            .await;
+        assert!(matches!(result, CallResult::Cancelled));
+    }
+}
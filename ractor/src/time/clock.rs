@@ -0,0 +1,206 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! A pluggable notion of time, so time-dependent actors (heartbeats,
+//! restart-intensity windows, RPC timeouts) can be driven deterministically
+//! in tests rather than waiting on wall-clock [Duration]s.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use tokio::sync::oneshot;
+
+use super::Duration;
+
+/// A source of time. [RealClock] is what every actor uses by default;
+/// [MockClock] lets tests advance a virtual clock instead.
+pub trait Clock: Send + Sync + 'static {
+    /// How much of this clock's time has elapsed since it was created
+    fn now(&self) -> Duration;
+
+    /// A future that resolves once `duration` of this clock's time has elapsed
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The default [Clock], backed by [tokio::time].
+pub struct RealClock {
+    start: tokio::time::Instant,
+}
+
+impl Default for RealClock {
+    fn default() -> Self {
+        Self {
+            start: tokio::time::Instant::now(),
+        }
+    }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+/// The process-wide default [RealClock], shared by every call that doesn't
+/// explicitly supply its own [Clock].
+pub fn real_clock() -> Arc<dyn Clock> {
+    static CLOCK: OnceLock<Arc<dyn Clock>> = OnceLock::new();
+    CLOCK
+        .get_or_init(|| Arc::new(RealClock::default()) as Arc<dyn Clock>)
+        .clone()
+}
+
+struct PendingTimer {
+    fire_at: Duration,
+    seq: u64,
+    notify: oneshot::Sender<()>,
+}
+
+impl PartialEq for PendingTimer {
+    fn eq(&self, other: &Self) -> bool {
+        (self.fire_at, self.seq) == (other.fire_at, other.seq)
+    }
+}
+impl Eq for PendingTimer {}
+impl PartialOrd for PendingTimer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for PendingTimer {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.fire_at, self.seq).cmp(&(other.fire_at, other.seq))
+    }
+}
+
+#[derive(Default)]
+struct MockClockState {
+    now: Duration,
+    next_seq: u64,
+    timers: BinaryHeap<Reverse<PendingTimer>>,
+}
+
+/// A virtual clock for tests: time only passes when [MockClock::advance] is
+/// called, and every timer scheduled at or before the new virtual time fires
+/// (in fire-time order) before `advance` resolves.
+#[derive(Clone, Default)]
+pub struct MockClock {
+    state: Arc<Mutex<MockClockState>>,
+}
+
+impl MockClock {
+    /// Create a new mock clock, with virtual time starting at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance the virtual clock by `delta`, firing every timer scheduled to
+    /// fire at or before the resulting time, in order. Firing a timer yields
+    /// to the executor before checking for the next one, so an interval
+    /// timer's next tick (re-registered once its `sleep` future resolves) is
+    /// picked up within the same `advance` if it also falls within the window.
+    /// `now` is advanced to each timer's own `fire_at` just before it fires
+    /// (rather than jumping straight to the target time), so a freshly
+    /// re-registered interval tick is scheduled relative to *its* fire time,
+    /// not one that's already run ahead to the end of the window.
+    pub async fn advance(&self, delta: Duration) {
+        let target = {
+            let state = self.state.lock().unwrap();
+            state.now + delta
+        };
+
+        loop {
+            let due = {
+                let mut state = self.state.lock().unwrap();
+                match state.timers.peek() {
+                    Some(Reverse(timer)) if timer.fire_at <= target => {
+                        let timer = state.timers.pop().map(|Reverse(t)| t);
+                        if let Some(timer) = &timer {
+                            state.now = timer.fire_at;
+                        }
+                        timer
+                    }
+                    _ => None,
+                }
+            };
+            match due {
+                Some(timer) => {
+                    let _ = timer.notify.send(());
+                    // let the woken task (e.g. an interval loop) re-register
+                    // its next timer before we look for the next due one
+                    tokio::task::yield_now().await;
+                }
+                None => break,
+            }
+        }
+
+        self.state.lock().unwrap().now = target;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        self.state.lock().unwrap().now
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut state = self.state.lock().unwrap();
+            let fire_at = state.now + duration;
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.timers.push(Reverse(PendingTimer {
+                fire_at,
+                seq,
+                notify: tx,
+            }));
+        }
+        async move {
+            let _ = rx.await;
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Regression test for a bug where `advance` bumped `now` straight to the
+    /// target up front: a re-registered interval tick would then compute its
+    /// `fire_at` from that already-advanced `now`, so `advance(3 * interval)`
+    /// fired an interval loop once instead of three times.
+    #[tokio::test]
+    async fn advance_fires_an_interval_loop_the_right_number_of_times() {
+        let clock = MockClock::new();
+        let interval = Duration::from_secs(1);
+        let fire_count = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+
+        let loop_clock = clock.clone();
+        let loop_count = fire_count.clone();
+        tokio::spawn(async move {
+            loop {
+                loop_clock.sleep(interval).await;
+                loop_count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        });
+
+        // give the spawned task a chance to register its first sleep before
+        // advancing, same as a freshly-started send_interval loop would have
+        tokio::task::yield_now().await;
+
+        clock.advance(interval * 3).await;
+
+        assert_eq!(fire_count.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright (c) Sean Lawlor
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree.
+
+//! Time-related re-exports and scheduling helpers used across the actor
+//! runtime: [send_interval] and [send_after] let an actor schedule messages
+//! to itself, as seen e.g. in a heartbeat set up from `pre_start`. Both
+//! consult a [Clock] (see [clock]), so they can be driven deterministically
+//! in tests via a [MockClock] instead of real wall-clock time.
+
+pub mod clock;
+
+pub use clock::{Clock, MockClock, RealClock};
+pub use tokio::time::{sleep, timeout, Duration, Instant};
+
+use std::sync::Arc;
+
+use crate::actor::actor_cell::WeakActorCell;
+use crate::Message;
+
+/// A handle to a scheduled, repeating or one-shot send set up by
+/// [send_interval]/[send_after]. Dropping the handle (or calling
+/// [TimerHandle::abort] explicitly) cancels all future ticks.
+pub struct TimerHandle {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TimerHandle {
+    /// Cancel this timer. Any tick already in flight still completes, but no
+    /// further ticks will be scheduled.
+    pub fn abort(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Repeatedly enqueue a message built from `msg_factory` onto `cell`'s
+/// message port every `interval`, until the returned [TimerHandle] is
+/// dropped/aborted or the actor itself stops. The timer holds only a
+/// [WeakActorCell], so it can never keep a stopped actor's state alive.
+/// Ticks against the process-wide [RealClock]; see [send_interval_with_clock]
+/// to drive it from a [MockClock] in tests.
+pub fn send_interval<TMsg, F>(cell: WeakActorCell, interval: Duration, msg_factory: F) -> TimerHandle
+where
+    TMsg: Message,
+    F: Fn() -> TMsg + Send + 'static,
+{
+    send_interval_with_clock(clock::real_clock(), cell, interval, msg_factory)
+}
+
+/// Like [send_interval], but ticking against an explicit [Clock] rather than
+/// the real one, so a test can drive it with a [MockClock].
+pub fn send_interval_with_clock<TMsg, F>(
+    clock: Arc<dyn Clock>,
+    cell: WeakActorCell,
+    interval: Duration,
+    msg_factory: F,
+) -> TimerHandle
+where
+    TMsg: Message,
+    F: Fn() -> TMsg + Send + 'static,
+{
+    let task = tokio::spawn(async move {
+        loop {
+            clock.sleep(interval).await;
+            let Some(cell) = cell.upgrade() else {
+                // the actor is gone: stop ticking rather than leak
+                break;
+            };
+            if cell.send_boxed_message(Box::new(msg_factory())).is_err() {
+                break;
+            }
+        }
+    });
+    TimerHandle { task }
+}
+
+/// Enqueue a single message built from `msg_factory` onto `cell`'s message
+/// port after `delay` elapses, unless the returned [TimerHandle] is
+/// dropped/aborted first. Waits out `delay` against the process-wide
+/// [RealClock]; see [send_after_with_clock] to drive it from a [MockClock]
+/// in tests.
+pub fn send_after<TMsg, F>(cell: WeakActorCell, delay: Duration, msg_factory: F) -> TimerHandle
+where
+    TMsg: Message,
+    F: FnOnce() -> TMsg + Send + 'static,
+{
+    send_after_with_clock(clock::real_clock(), cell, delay, msg_factory)
+}
+
+/// Like [send_after], but waiting out `delay` against an explicit [Clock]
+/// rather than the real one, so a test can drive it with a [MockClock].
+pub fn send_after_with_clock<TMsg, F>(
+    clock: Arc<dyn Clock>,
+    cell: WeakActorCell,
+    delay: Duration,
+    msg_factory: F,
+) -> TimerHandle
+where
+    TMsg: Message,
+    F: FnOnce() -> TMsg + Send + 'static,
+{
+    let task = tokio::spawn(async move {
+        clock.sleep(delay).await;
+        if let Some(cell) = cell.upgrade() {
+            let _ = cell.send_boxed_message(Box::new(msg_factory()));
+        }
+    });
+    TimerHandle { task }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::actor_cell::{ActorCell, ActorPortSet};
+    use tokio::sync::mpsc;
+
+    /// Build a bare [ActorCell] with no actor runtime behind it, just to
+    /// drive [send_interval_with_clock]/[send_after_with_clock] against and
+    /// inspect what lands on its message port.
+    fn bare_cell() -> (ActorCell, mpsc::UnboundedReceiver<Box<dyn std::any::Any + Send>>) {
+        let (signal_tx, _signal_rx) = mpsc::channel(1);
+        let (stop_tx, _stop_rx) = mpsc::channel(1);
+        let (supervision_tx, _supervision_rx) = mpsc::channel(1);
+        let (message_tx, message_rx) = mpsc::unbounded_channel();
+        let cell = ActorCell::new(
+            None,
+            ActorPortSet {
+                signal_tx,
+                stop_tx,
+                supervision_tx,
+                message_tx,
+            },
+        );
+        (cell, message_rx)
+    }
+
+    struct Tick;
+
+    #[tokio::test]
+    async fn advancing_the_mock_clock_delivers_interval_ticks() {
+        let (cell, mut message_rx) = bare_cell();
+        let clock = MockClock::new();
+        let _handle = send_interval_with_clock(
+            std::sync::Arc::new(clock.clone()) as std::sync::Arc<dyn Clock>,
+            cell.downgrade(),
+            Duration::from_secs(1),
+            || Tick,
+        );
+
+        // let the timer task register its first sleep before advancing
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(3)).await;
+
+        for _ in 0..3 {
+            let boxed = message_rx.recv().await.expect("expected a tick");
+            assert!(boxed.downcast_ref::<Tick>().is_some());
+        }
+        assert!(message_rx.try_recv().is_err(), "no extra ticks expected");
+    }
+
+    #[tokio::test]
+    async fn dropping_the_timer_handle_cancels_future_ticks() {
+        let (cell, mut message_rx) = bare_cell();
+        let clock = MockClock::new();
+        let handle = send_interval_with_clock(
+            std::sync::Arc::new(clock.clone()) as std::sync::Arc<dyn Clock>,
+            cell.downgrade(),
+            Duration::from_secs(1),
+            || Tick,
+        );
+
+        // let the timer task register its first sleep before advancing
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(1)).await;
+        assert!(message_rx.recv().await.is_some());
+
+        drop(handle);
+        clock.advance(Duration::from_secs(5)).await;
+        assert!(message_rx.try_recv().is_err(), "handle was dropped, no further ticks expected");
+    }
+
+    #[tokio::test]
+    async fn send_after_delivers_exactly_one_message() {
+        let (cell, mut message_rx) = bare_cell();
+        let clock = MockClock::new();
+        let _handle = send_after_with_clock(
+            std::sync::Arc::new(clock.clone()) as std::sync::Arc<dyn Clock>,
+            cell.downgrade(),
+            Duration::from_secs(2),
+            || Tick,
+        );
+
+        // let the timer task register its sleep before advancing
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(2)).await;
+        assert!(message_rx.recv().await.is_some());
+        assert!(message_rx.try_recv().is_err(), "send_after should only fire once");
+    }
+}